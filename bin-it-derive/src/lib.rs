@@ -0,0 +1,670 @@
+//! Derive macros for Bin-It's `Writable`, `Readable` and `SizeHint` traits.
+//!
+//! Use them through the `bin-it` crate (with its default `derive` feature) rather than directly:
+//!
+//! ```ignore
+//! use bin_it::{BinRead, BinWrite};
+//!
+//! #[derive(BinWrite, BinRead)]
+//! struct Header {
+//!   magic: u32,
+//!   name: String,
+//! }
+//! ```
+//!
+//! `#[derive(SizeHint)]` sums the hints of the fields (plus the tag, for enums), following the same
+//! `#[bin(...)]` attributes as `BinWrite`.
+//!
+//! `#[derive(Binary)]` is shorthand for `#[derive(BinWrite, BinRead)]`.
+//!
+//! Fields of type `PhantomData<T>` (and other zero-sized `()` fields) are written as nothing, and a
+//! type parameter used only inside `PhantomData` is not required to implement the traits.
+//!
+//! Struct fields are written in declaration order with no framing. Enums are written as a u32 tag
+//! holding the variant's position (not its discriminant value), followed by the variant's fields.
+//!
+//! Attributes are given as `#[bin(...)]` (`#[binary(...)]` is accepted as an alias):
+//!  * `#[bin(try_from)]` on a fixed-size struct implements `TryFrom<&[u8]>` (see below).
+//!  * `#[bin(by_name)]` on an enum writes the variant's name as a string instead of a numeric tag,
+//!    so variants can be added or reordered without breaking old data. Unknown names fail to read.
+//!  * `#[bin(tag = "u8")]` on an enum writes the tag as a `u8` (or `"u16"`, or the default `"u32"`),
+//!    for compact encodings of small enums. Deriving fails if the enum has too many variants for the
+//!    chosen width.
+//!  * `#[bin(fixed = 16)]` on a `[T; 16]` field spells out that the array is written as exactly 16
+//!    elements with no length prefix, unlike a `Vec`. Array fields use this encoding even without
+//!    the attribute; it exists to document intent and fails to compile if `N` doesn't match, whether
+//!    `N` is a literal or a named constant. For a const generic `N`, the error appears wherever the
+//!    struct is written or read with a mismatched length.
+//!
+//! `BinRead` also sets `Readable::SERIALIZED_SIZE`: the sum of the fields' sizes for a struct, or the
+//! tag plus the shared field size for an enum whose variants are all the same size, and `None`
+//! whenever a field (or a `by_name` tag) has a variable size.
+//!
+//! `#[bin(try_from)]` on a struct also makes `BinRead` implement `TryFrom<&[u8]>`, which decodes a
+//! slice of exactly the struct's `SERIALIZED_SIZE` and rejects any other length with `SizeMismatch`.
+//! Deriving it fails to compile if the struct has a variable size, e.g. a `String` field.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Spacing, TokenStream as TokenStream2, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::{parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Fields, Generics, Ident};
+
+/// Derives `bin_it::Writable` for a struct or enum.
+#[proc_macro_derive(BinWrite, attributes(bin, binary))]
+pub fn derive_bin_write(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  expand_write(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Derives `bin_it::Readable` for a struct or enum.
+#[proc_macro_derive(BinRead, attributes(bin, binary))]
+pub fn derive_bin_read(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  expand_read(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Derives both `bin_it::Writable` and `bin_it::Readable`, like `#[derive(BinWrite, BinRead)]`.
+#[proc_macro_derive(Binary, attributes(bin, binary))]
+pub fn derive_binary(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  let expanded = expand_write(&input).and_then(|write| {
+    let read = expand_read(&input)?;
+    Ok(quote! { #write #read })
+  });
+  expanded.unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Derives `bin_it::SizeHint` for a struct or enum, matching the layout `BinWrite` produces.
+#[proc_macro_derive(SizeHint, attributes(bin, binary))]
+pub fn derive_size_hint(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  expand_size_hint(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Options set with `#[bin(...)]` on the type itself.
+#[derive(Default)]
+struct ContainerAttrs {
+  by_name: bool,
+  tag: TagWidth,
+  try_from: bool,
+}
+
+/// The integer type a numeric enum tag is written as.
+#[derive(Clone, Copy, Default)]
+enum TagWidth {
+  U8,
+  U16,
+  #[default]
+  U32,
+}
+
+impl TagWidth {
+  fn size(self) -> usize {
+    match self {
+      TagWidth::U8 => 1,
+      TagWidth::U16 => 2,
+      TagWidth::U32 => 4,
+    }
+  }
+
+  /// Tokens writing `index` as a tag of this width.
+  fn write(self, index: usize) -> TokenStream2 {
+    match self {
+      TagWidth::U8 => {
+        let index = index as u8;
+        quote! { __writer.write_u8(#index); }
+      }
+      TagWidth::U16 => {
+        let index = index as u16;
+        quote! { __writer.write_u16(#index); }
+      }
+      TagWidth::U32 => {
+        let index = index as u32;
+        quote! { __writer.write_u32(#index); }
+      }
+    }
+  }
+
+  /// A pattern matching `index` read back with `read`.
+  fn pattern(self, index: usize) -> TokenStream2 {
+    match self {
+      TagWidth::U8 => {
+        let index = index as u8;
+        quote!(#index)
+      }
+      TagWidth::U16 => {
+        let index = index as u16;
+        quote!(#index)
+      }
+      TagWidth::U32 => {
+        let index = index as u32;
+        quote!(#index)
+      }
+    }
+  }
+
+  /// An expression reading a tag of this width.
+  fn read(self) -> TokenStream2 {
+    match self {
+      TagWidth::U8 => quote!(__reader.read_u8()?),
+      TagWidth::U16 => quote!(__reader.read_u16()?),
+      TagWidth::U32 => quote!(__reader.read_u32()?),
+    }
+  }
+
+  /// Fails if an enum with `variants` variants cannot number them all at this width.
+  fn check(self, input: &DeriveInput, variants: usize) -> syn::Result<()> {
+    let max = match self {
+      TagWidth::U8 => 1 << 8,
+      TagWidth::U16 => 1 << 16,
+      TagWidth::U32 => return Ok(()),
+    };
+    if variants > max {
+      let message = format!("enum has {variants} variants, more than a {}-byte tag can hold", self.size());
+      return Err(syn::Error::new_spanned(&input.ident, message));
+    }
+    Ok(())
+  }
+}
+
+impl ContainerAttrs {
+  fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+    let mut parsed = ContainerAttrs::default();
+    for attr in attrs.iter().filter(|a| is_bin_attr(a)) {
+      attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("by_name") {
+          parsed.by_name = true;
+          Ok(())
+        } else if meta.path.is_ident("try_from") {
+          parsed.try_from = true;
+          Ok(())
+        } else if meta.path.is_ident("tag") {
+          let value: syn::LitStr = meta.value()?.parse()?;
+          parsed.tag = match value.value().as_str() {
+            "u8" => TagWidth::U8,
+            "u16" => TagWidth::U16,
+            "u32" => TagWidth::U32,
+            _ => return Err(syn::Error::new_spanned(value, "tag must be \"u8\", \"u16\" or \"u32\"")),
+          };
+          Ok(())
+        } else {
+          Err(meta.error("unknown bin attribute"))
+        }
+      })?;
+    }
+    Ok(parsed)
+  }
+}
+
+/// Options set with `#[bin(...)]` on a single field.
+#[derive(Default)]
+struct FieldAttrs {
+  fixed: Option<usize>,
+}
+
+impl FieldAttrs {
+  fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+    let mut parsed = FieldAttrs::default();
+    for attr in attrs.iter().filter(|a| is_bin_attr(a)) {
+      attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("fixed") {
+          let value: syn::LitInt = meta.value()?.parse()?;
+          parsed.fixed = Some(value.base10_parse()?);
+          Ok(())
+        } else {
+          Err(meta.error("unknown bin attribute"))
+        }
+      })?;
+    }
+    Ok(parsed)
+  }
+}
+
+/// How a field is put on the wire.
+enum FieldCodec {
+  /// Through the field type's own `Writable`/`Readable` impls.
+  Value,
+  /// As a fixed-size array: `write_array`/`read_array`, with no length prefix. `check` holds a
+  /// const assertion that a `fixed` attribute matches a length the macro could not evaluate.
+  Array { check: TokenStream2 },
+}
+
+impl FieldCodec {
+  fn of(field: &syn::Field) -> syn::Result<Self> {
+    let attrs = FieldAttrs::parse(&field.attrs)?;
+    match (&field.ty, attrs.fixed) {
+      (syn::Type::Array(array), fixed) => {
+        let check = match (fixed, array_len(array)) {
+          (Some(fixed), Some(len)) if fixed != len => {
+            let message = format!("fixed = {fixed} does not match the array length {len}");
+            return Err(syn::Error::new_spanned(&field.ty, message));
+          }
+          // A named constant or const generic length is only known to the compiler.
+          (Some(fixed), None) => {
+            let len = &array.len;
+            quote! { const { assert!((#len) == #fixed, "bin(fixed) does not match the array length") }; }
+          }
+          _ => TokenStream2::new(),
+        };
+        Ok(FieldCodec::Array { check })
+      }
+      (_, Some(_)) => Err(syn::Error::new_spanned(&field.ty, "fixed can only be used on array fields")),
+      (_, None) => Ok(FieldCodec::Value),
+    }
+  }
+}
+
+/// Returns the length of an array type whose length is written as an integer literal.
+fn array_len(array: &syn::TypeArray) -> Option<usize> {
+  match &array.len {
+    syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(len), .. }) => len.base10_parse().ok(),
+    _ => None,
+  }
+}
+
+/// Builds a statement writing each field from its `field_bindings` identifier.
+fn write_fields(fields: &Fields) -> syn::Result<Vec<TokenStream2>> {
+  fields
+    .iter()
+    .zip(field_bindings(fields))
+    .map(|(field, binding)| {
+      Ok(match FieldCodec::of(field)? {
+        FieldCodec::Value => quote! { ::bin_it::Writable::write_to(#binding, __writer); },
+        FieldCodec::Array { check } => quote! { #check __writer.write_array(#binding); },
+      })
+    })
+    .collect()
+}
+
+/// Builds an expression reading each field, propagating errors with `?`.
+fn read_fields(fields: &Fields) -> syn::Result<Vec<TokenStream2>> {
+  fields
+    .iter()
+    .map(|field| {
+      Ok(match FieldCodec::of(field)? {
+        FieldCodec::Value => quote! { ::bin_it::Readable::read_from(__reader)? },
+        FieldCodec::Array { check } => quote! { { #check __reader.read_array()? } },
+      })
+    })
+    .collect()
+}
+
+/// Builds an expression for the serialized size of each field from its `field_bindings` identifier.
+fn size_hint_fields(fields: &Fields) -> syn::Result<Vec<TokenStream2>> {
+  fields
+    .iter()
+    .zip(field_bindings(fields))
+    .map(|(field, binding)| {
+      Ok(match FieldCodec::of(field)? {
+        FieldCodec::Value => quote! { ::bin_it::SizeHint::serialized_size(#binding) },
+        FieldCodec::Array { .. } => quote! { #binding.iter().map(::bin_it::SizeHint::serialized_size).sum::<usize>() },
+      })
+    })
+    .collect()
+}
+
+fn is_bin_attr(attr: &Attribute) -> bool {
+  attr.path().is_ident("bin") || attr.path().is_ident("binary")
+}
+
+/// Adds `bound` to every type parameter of `input` that its fields actually serialize. Parameters
+/// that only appear inside `PhantomData` are left unbounded, since nothing of that type is written.
+fn add_bounds(input: &DeriveInput, bound: syn::TypeParamBound) -> Generics {
+  let field_types: Vec<&syn::Type> = match &input.data {
+    Data::Struct(data) => data.fields.iter().map(|f| &f.ty).collect(),
+    Data::Enum(data) => data.variants.iter().flat_map(|v| v.fields.iter().map(|f| &f.ty)).collect(),
+    Data::Union(_) => Vec::new(),
+  };
+  let mut generics = input.generics.clone();
+  for param in generics.type_params_mut() {
+    if field_types.iter().any(|ty| mentions_outside_phantom(ty.to_token_stream(), &param.ident)) {
+      param.bounds.push(bound.clone());
+    }
+  }
+  generics
+}
+
+/// Returns true if `param` appears in `tokens` anywhere other than the arguments of a `PhantomData`.
+fn mentions_outside_phantom(tokens: TokenStream2, param: &Ident) -> bool {
+  let mut tokens = tokens.into_iter().peekable();
+  while let Some(token) = tokens.next() {
+    match token {
+      TokenTree::Ident(ident) if ident == "PhantomData" => {
+        if !matches!(tokens.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '<') {
+          continue;
+        }
+        // Skip to the matching `>`, ignoring the `>` of any `->` in a function type argument.
+        let mut depth = 0;
+        let mut after_dash = false;
+        for token in tokens.by_ref() {
+          if let TokenTree::Punct(p) = &token {
+            match p.as_char() {
+              '<' => depth += 1,
+              '>' if !after_dash => depth -= 1,
+              _ => {}
+            }
+            after_dash = p.as_char() == '-' && p.spacing() == Spacing::Joint;
+            if depth == 0 {
+              break;
+            }
+          } else {
+            after_dash = false;
+          }
+        }
+      }
+      TokenTree::Ident(ident) if ident == *param => return true,
+      TokenTree::Group(group) if mentions_outside_phantom(group.stream(), param) => return true,
+      _ => {}
+    }
+  }
+  false
+}
+
+/// Returns the identifiers a match arm binds the fields to: field names, or `__f0`, `__f1`, ... for
+/// tuple fields.
+fn field_bindings(fields: &Fields) -> Vec<Ident> {
+  fields
+    .iter()
+    .enumerate()
+    .map(|(i, f)| f.ident.clone().unwrap_or_else(|| format_ident!("__f{}", i)))
+    .collect()
+}
+
+/// Builds a pattern for `path` that binds each field to its `field_bindings` identifier.
+fn fields_pattern(path: TokenStream2, fields: &Fields) -> TokenStream2 {
+  let bindings = field_bindings(fields);
+  match fields {
+    Fields::Named(_) => quote! { #path { #(#bindings),* } },
+    Fields::Unnamed(_) => quote! { #path ( #(#bindings),* ) },
+    Fields::Unit => path,
+  }
+}
+
+/// Builds a constructor for `path` with each field set to the matching entry of `values`.
+fn fields_shape(path: TokenStream2, fields: &Fields, values: &[TokenStream2]) -> TokenStream2 {
+  match fields {
+    Fields::Named(named) => {
+      let names = named.named.iter().map(|f| &f.ident);
+      quote! { #path { #(#names: #values),* } }
+    }
+    Fields::Unnamed(_) => quote! { #path ( #(#values),* ) },
+    Fields::Unit => path,
+  }
+}
+
+fn expand_write(input: &DeriveInput) -> syn::Result<TokenStream2> {
+  let attrs = ContainerAttrs::parse(&input.attrs)?;
+  let name = &input.ident;
+  let generics = add_bounds(input, parse_quote!(::bin_it::Writable));
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+  let body = match &input.data {
+    Data::Struct(data) => {
+      let writes = write_fields(&data.fields)?;
+      let pattern = fields_pattern(quote!(Self), &data.fields);
+      quote! {
+        let #pattern = self;
+        #(#writes)*
+      }
+    }
+    Data::Enum(data) => {
+      attrs.tag.check(input, data.variants.len())?;
+      let arms = data
+        .variants
+        .iter()
+        .enumerate()
+        .map(|(index, variant)| {
+          let ident = &variant.ident;
+          let writes = write_fields(&variant.fields)?;
+          let pattern = fields_pattern(quote!(Self::#ident), &variant.fields);
+          let tag = if attrs.by_name {
+            let label = ident.to_string();
+            quote! { __writer.write_string(#label); }
+          } else {
+            attrs.tag.write(index)
+          };
+          Ok(quote! {
+            #pattern => {
+              #tag
+              #(#writes)*
+            }
+          })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+      quote! {
+        match self {
+          #(#arms)*
+        }
+      }
+    }
+    Data::Union(_) => return Err(syn::Error::new_spanned(&input.ident, "BinWrite cannot be derived for unions")),
+  };
+
+  Ok(quote! {
+    impl #impl_generics ::bin_it::Writable for #name #ty_generics #where_clause {
+      #[allow(unused_variables)]
+      fn write_to<__S: ::bin_it::Sink>(&self, __writer: &mut ::bin_it::BinaryWriter<__S>) {
+        #body
+      }
+    }
+  })
+}
+
+fn expand_read(input: &DeriveInput) -> syn::Result<TokenStream2> {
+  let attrs = ContainerAttrs::parse(&input.attrs)?;
+  let name = &input.ident;
+  let generics = add_bounds(input, parse_quote!(::bin_it::Readable));
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+  let size = match &input.data {
+    Data::Struct(data) => fields_size(&data.fields),
+    // A name tag's length varies, so only numeric tags can give a fixed size.
+    Data::Enum(data) if !attrs.by_name => {
+      let tag_size = attrs.tag.size();
+      let sizes = data.variants.iter().map(|variant| fields_size(&variant.fields));
+      quote! {
+        {
+          let mut __size: Option<Option<usize>> = None;
+          #(
+            __size = match (__size, #sizes) {
+              (None, __variant) => Some(__variant),
+              (Some(Some(__a)), Some(__b)) if __a == __b => Some(Some(__a)),
+              _ => Some(None),
+            };
+          )*
+          match __size {
+            Some(Some(__fields)) => Some(#tag_size + __fields),
+            _ => None,
+          }
+        }
+      }
+    }
+    _ => quote!(None),
+  };
+
+  let body = match &input.data {
+    Data::Struct(data) => {
+      let values = read_fields(&data.fields)?;
+      let value = fields_shape(quote!(Self), &data.fields, &values);
+      quote! { Ok(#value) }
+    }
+    Data::Enum(data) => {
+      attrs.tag.check(input, data.variants.len())?;
+      let arms = data
+        .variants
+        .iter()
+        .enumerate()
+        .map(|(index, variant)| {
+          let ident = &variant.ident;
+          let values = read_fields(&variant.fields)?;
+          let value = fields_shape(quote!(Self::#ident), &variant.fields, &values);
+          Ok(if attrs.by_name {
+            let label = ident.to_string();
+            quote! { #label => Ok(#value), }
+          } else {
+            let index = attrs.tag.pattern(index);
+            quote! { #index => Ok(#value), }
+          })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+      if attrs.by_name {
+        quote! {
+          let __name = __reader.read_string()?;
+          match __name.as_str() {
+            #(#arms)*
+            _ => Err(::bin_it::BinaryError::UnknownVariantName(__name)),
+          }
+        }
+      } else {
+        let read_tag = attrs.tag.read();
+        quote! {
+          match #read_tag {
+            #(#arms)*
+            tag => Err(::bin_it::BinaryError::UnknownVariant(u32::from(tag))),
+          }
+        }
+      }
+    }
+    Data::Union(_) => return Err(syn::Error::new_spanned(&input.ident, "BinRead cannot be derived for unions")),
+  };
+
+  // The length check uses the same `SERIALIZED_SIZE` as the `Readable` impl, so the two agree for
+  // nested derived structs and `PhantomData` fields too. A variable size is a compile error: up
+  // front for plain structs, and when the impl is instantiated for generic ones.
+  let try_from = match &input.data {
+    Data::Struct(_) if attrs.try_from => {
+      let assert_fixed = input.generics.params.is_empty().then(|| {
+        quote! {
+          const _: () = assert!(
+            <#name as ::bin_it::Readable>::SERIALIZED_SIZE.is_some(),
+            "#[bin(try_from)] requires a struct with a fixed SERIALIZED_SIZE"
+          );
+        }
+      });
+      Some(quote! {
+        #assert_fixed
+
+        impl #impl_generics ::core::convert::TryFrom<&[u8]> for #name #ty_generics #where_clause {
+          type Error = ::bin_it::BinaryError;
+
+          fn try_from(__bytes: &[u8]) -> Result<Self, Self::Error> {
+            let __size = const {
+              match <Self as ::bin_it::Readable>::SERIALIZED_SIZE {
+                Some(__size) => __size,
+                None => panic!("#[bin(try_from)] requires a struct with a fixed SERIALIZED_SIZE"),
+              }
+            };
+            if __bytes.len() != __size {
+              return Err(::bin_it::BinaryError::SizeMismatch { expected: __size, found: __bytes.len() });
+            }
+            ::bin_it::Readable::read_from(&mut ::bin_it::BinaryReader::new(__bytes))
+          }
+        }
+      })
+    }
+    Data::Struct(_) => None,
+    _ if attrs.try_from => return Err(syn::Error::new_spanned(&input.ident, "try_from can only be used on structs")),
+    _ => None,
+  };
+
+  Ok(quote! {
+    impl #impl_generics ::bin_it::Readable for #name #ty_generics #where_clause {
+      const SERIALIZED_SIZE: Option<usize> = #size;
+
+      #[allow(unused_variables)]
+      fn read_from(__reader: &mut ::bin_it::BinaryReader<'_>) -> Result<Self, ::bin_it::BinaryError> {
+        #body
+      }
+    }
+
+    #try_from
+  })
+}
+
+/// Builds a const expression summing the fields' `Readable::SERIALIZED_SIZE`s, `None` if any is.
+fn fields_size(fields: &Fields) -> TokenStream2 {
+  let sizes = fields.iter().map(|f| type_size(&f.ty));
+  quote! {
+    {
+      let mut __size: Option<usize> = Some(0);
+      #(
+        __size = match (__size, #sizes) {
+          (Some(__a), Some(__b)) => Some(__a + __b),
+          _ => None,
+        };
+      )*
+      __size
+    }
+  }
+}
+
+/// Builds a const expression for the `SERIALIZED_SIZE` of `ty`, computing arrays from their elements.
+fn type_size(ty: &syn::Type) -> TokenStream2 {
+  match ty {
+    syn::Type::Array(array) => {
+      let element = type_size(&array.elem);
+      let len = &array.len;
+      quote! {
+        match #element {
+          Some(__element) => Some(__element * (#len)),
+          None => None,
+        }
+      }
+    }
+    _ => quote!(<#ty as ::bin_it::Readable>::SERIALIZED_SIZE),
+  }
+}
+
+fn expand_size_hint(input: &DeriveInput) -> syn::Result<TokenStream2> {
+  let attrs = ContainerAttrs::parse(&input.attrs)?;
+  let name = &input.ident;
+  let generics = add_bounds(input, parse_quote!(::bin_it::SizeHint));
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+  let body = match &input.data {
+    Data::Struct(data) => {
+      let sizes = size_hint_fields(&data.fields)?;
+      let pattern = fields_pattern(quote!(Self), &data.fields);
+      quote! {
+        let #pattern = self;
+        0 #( + #sizes )*
+      }
+    }
+    Data::Enum(data) => {
+      let arms = data
+        .variants
+        .iter()
+        .map(|variant| {
+          let ident = &variant.ident;
+          let sizes = size_hint_fields(&variant.fields)?;
+          let pattern = fields_pattern(quote!(Self::#ident), &variant.fields);
+          let tag = if attrs.by_name {
+            let label = ident.to_string();
+            quote! { 4 + #label.len() }
+          } else {
+            let size = attrs.tag.size();
+            quote! { #size }
+          };
+          Ok(quote! {
+            #pattern => #tag #( + #sizes )*,
+          })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+      quote! {
+        match self {
+          #(#arms)*
+        }
+      }
+    }
+    Data::Union(_) => return Err(syn::Error::new_spanned(&input.ident, "SizeHint cannot be derived for unions")),
+  };
+
+  Ok(quote! {
+    impl #impl_generics ::bin_it::SizeHint for #name #ty_generics #where_clause {
+      #[allow(unused_variables)]
+      fn serialized_size(&self) -> usize {
+        #body
+      }
+    }
+  })
+}
@@ -1,546 +1,6672 @@
-/// # Bin-It
-///
-/// **Bin-It** is a simple, efficient Rust library for binary serialization and deserialization. With a focus on
-/// performance and ease of use, Bin-It lets you seamlessly serialize Rust types into compact binary formats and
-/// read them back with precision. Whether you're storing data in binary files, transmitting data over networks,
-/// or handling low-level byte operations, **Bin-It** has you covered.
-///
-/// ## Features
-///
-/// - Serialize and deserialize common primitive types (`u8`, `i16`, `f32`, etc.).
-/// - Supports serialization of strings and collections (e.g., `Vec<u8>`, `Vec<f64>`, etc.).
-/// - Consistent, little-endian encoding for cross-platform compatibility.
-/// - Minimal dependencies for fast, lightweight binary manipulation.
-///
-/// ## Usage
-///
-/// ### Writing Data
-///
-/// The BinaryWriter struct allows you to serialize various data types into a binary buffer:
-///
-/// ```rust
-/// use bin_it::BinaryWriter;
-///
-/// fn main() {
-///     let mut writer = BinaryWriter::new();
-///     writer.write_u32(42);
-///     writer.write_string("Hello, Bin-It!");
-///     writer.write_f64(3.14159);
-///
-///     let data = writer.get_data();
-///     // Now `data` contains the binary representation of the serialized values.
-/// }
-/// ```
-///
-///
-/// ### Reading Data
-///
-/// The BinaryReader struct lets you deserialize the binary data back into Rust types:
-///
-/// ```rust
-/// use bin_it::BinaryReader;
-///
-/// fn main() {
-///     // Ensure `data` has enough bytes for the expected reads
-///     let data = vec![42, 0, 0, 0]; // Sufficient data for a u32
-///     let mut reader = BinaryReader::new(&data);
-///
-///     match reader.read_u32() {
-///         Ok(number) => println!("Number: {}", number),
-///         Err(e) => println!("Error reading u32: {}", e),
-///     }
-/// }
-/// ```
-///
-/// ## Supported Data Types
-///
-/// **Bin-It** supports writing and reading of:
-///  * Primitives: u8, i8, u16, i16, u32, i32, u64, i64, f32, f64, and bool.
-///  * Strings: UTF-8 strings serialized with length-prefix encoding.
-///  * Collections: Fixed-size collections, such as Vec<T> for supported types.
+//! # Bin-It
+//!
+//! **Bin-It** is a simple, efficient Rust library for binary serialization and deserialization. With a focus on
+//! performance and ease of use, Bin-It lets you seamlessly serialize Rust types into compact binary formats and
+//! read them back with precision. Whether you're storing data in binary files, transmitting data over networks,
+//! or handling low-level byte operations, **Bin-It** has you covered.
+//!
+//! ## Features
+//!
+//! - Serialize and deserialize common primitive types (`u8`, `i16`, `f32`, etc.).
+//! - Supports serialization of strings and collections (e.g., `Vec<u8>`, `Vec<f64>`, etc.).
+//! - Consistent, little-endian encoding for cross-platform compatibility.
+//! - Minimal dependencies for fast, lightweight binary manipulation.
+//!
+//! ## Usage
+//!
+//! ### Writing Data
+//!
+//! The BinaryWriter struct allows you to serialize various data types into a binary buffer:
+//!
+//! ```rust
+//! use bin_it::BinaryWriter;
+//!
+//! fn main() {
+//!     let mut writer = BinaryWriter::new();
+//!     writer.write_u32(42);
+//!     writer.write_string("Hello, Bin-It!");
+//!     writer.write_f64(3.14159);
+//!
+//!     let data = writer.get_data();
+//!     // Now `data` contains the binary representation of the serialized values.
+//! }
+//! ```
+//!
+//! Write methods return `&mut Self`, so calls can be chained:
+//!
+//! ```rust
+//! use bin_it::BinaryWriter;
+//!
+//! let mut writer = BinaryWriter::new();
+//! writer.write_u32(1).write_string("x").write_bool(true);
+//! ```
+//!
+//!
+//! ### Reading Data
+//!
+//! The BinaryReader struct lets you deserialize the binary data back into Rust types:
+//!
+//! ```rust
+//! use bin_it::BinaryReader;
+//!
+//! fn main() {
+//!     // Ensure `data` has enough bytes for the expected reads
+//!     let data = vec![42, 0, 0, 0]; // Sufficient data for a u32
+//!     let mut reader = BinaryReader::new(&data);
+//!
+//!     match reader.read_u32() {
+//!         Ok(number) => println!("Number: {}", number),
+//!         Err(e) => println!("Error reading u32: {}", e),
+//!     }
+//! }
+//! ```
+//!
+//! ## Supported Data Types
+//!
+//! **Bin-It** supports writing and reading of:
+//!  * Primitives: u8, i8, u16, i16, u32, i32, u64, i64, f32, f64, and bool.
+//!  * Pointer-width integers: usize and isize, always serialized as 64-bit values.
+//!  * Strings: UTF-8 strings serialized with length-prefix encoding.
+//!  * Chars: Unicode scalar values serialized as u32 code points.
+//!  * Collections: Fixed-size collections, such as Vec<T> for supported types.
+//!  * Arrays: `[T; N]`, written as its `N` elements with no length prefix.
+//!  * Tuples: up to eight `Writable`/`Readable` elements, serialized in order.
+//!  * Options: a u8 presence byte (0 for `None`, 1 for `Some`) followed by the value, or for vectors
+//!    a packed bitmap of present values with `write_vec_option_packed`.
+//!  * Results: a u8 tag (0 for `Ok`, 1 for `Err`) followed by the payload.
+//!  * Ranges: `Range` and `RangeInclusive`, written as `start` then `end`.
+//!  * IP addresses: `Ipv4Addr` and `Ipv6Addr`, written as their 4 or 16 octets in network order.
+//!  * Maps: HashMap and BTreeMap, serialized as a u32 count followed by key/value pairs.
+//!  * Sets: HashSet and BTreeSet, serialized like a Vec<T>; BTreeSet (and `write_hash_set_sorted`) in sorted order.
+//!  * Generic values: any type implementing the `Writable`/`Readable` traits, including nested `Vec<Vec<T>>`.
 
+// Lets the derive macros' `::bin_it::` paths resolve inside this crate's own tests.
+extern crate self as bin_it;
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::TryInto;
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::io;
+use std::marker::PhantomData;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::ops::{Deref, Range, RangeInclusive};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
+/// Derive macros for `Writable` (`BinWrite`), `Readable` (`BinRead`), both at once (`Binary`) and
+/// `SizeHint`, enabled by the `derive` feature.
+#[cfg(feature = "derive")]
+pub use bin_it_derive::{BinRead, BinWrite, Binary, SizeHint};
 
-/// BinaryWriter is used to serialize various data types into a byte buffer.
-pub struct BinaryWriter {
-  data: Vec<u8>,
+/// Re-exports the commonly used Bin-It items, so `use bin_it::prelude::*;` brings them all into scope.
+pub mod prelude {
+  pub use crate::{
+    BinaryError, BinaryReader, BinaryWriter, Checkpoint, ChecksumKind, Endianness, FlagSet, FrameDecoder, LengthSlot, PackedStrings,
+    PrefixWidth, Readable, SerializedData, Sink, SizeHint, StringInterner, Token, Writable,
+  };
+  #[cfg(feature = "derive")]
+  pub use crate::{BinRead, BinWrite, Binary};
 }
 
-impl BinaryWriter {
-  /// Creates a new BinaryWriter with an empty buffer.
-  pub fn new() -> Self {
-    BinaryWriter { data: Vec::new() }
-  }
+/// BinaryError describes why a value could not be read from a byte buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BinaryError {
+  /// The buffer ended before the requested number of bytes could be read: a read at byte `offset`
+  /// `needed` that many bytes but only `available` were left (0 for an empty or fully consumed buffer).
+  UnexpectedEof { needed: usize, available: usize, offset: usize },
+  /// A string's bytes were not valid UTF-8; `offset` is the position of the first invalid byte.
+  InvalidUtf8 { error: std::str::Utf8Error, offset: usize },
+  /// The u32 at byte `offset` was not a valid Unicode scalar value.
+  InvalidChar { value: u32, offset: usize },
+  /// The writer's sink failed with the given I/O error.
+  Io(io::ErrorKind),
+  /// A length does not fit in the prefix chosen to encode it.
+  LengthOverflow { length: usize, max: u64 },
+  /// An offset in a packed string table is out of order or not on a character boundary.
+  InvalidOffset { index: usize },
+  /// A derived enum's numeric tag does not match any variant.
+  UnknownVariant(u32),
+  /// A derived `by_name` enum's name does not match any variant.
+  UnknownVariantName(String),
+  /// Two slices that must be written together have different lengths.
+  LengthMismatch { left: usize, right: usize },
+  /// A write would have grown the writer past its maximum size.
+  SizeLimitExceeded { limit: usize },
+  /// A u64 value does not fit in this target's usize.
+  UsizeOverflow(u64),
+  /// An i64 value does not fit in this target's isize.
+  IsizeOverflow(i64),
+  /// A `LengthSlot` was filled on a writer other than the one that reserved it.
+  ForeignLengthSlot,
+  /// Bytes checked with `expect_bytes` (typically a file's magic number) did not match.
+  MagicMismatch { expected: Vec<u8>, found: Vec<u8> },
+  /// A set read with duplicate checking contained the element at `index` more than once.
+  DuplicateElement { index: usize },
+  /// A format version header is outside the range the reader supports.
+  UnsupportedVersion { version: u16, min: u16, max: u16 },
+  /// A buffer converted into a fixed-size type is not exactly that type's size.
+  SizeMismatch { expected: usize, found: usize },
+  /// A decoded value is well-formed but not allowed where it was read, e.g. a NaN read with
+  /// `read_f64_finite`.
+  InvalidValue(String),
+  /// A reader was finished with `remaining` bytes still unread.
+  TrailingBytes { remaining: usize },
+  /// The checksum footer of a buffer opened with `BinaryReader::new_verified` does not match its body.
+  ChecksumMismatch { expected: u32, found: u32 },
+}
 
-  /// Returns a reference to the internal byte buffer.
-  pub fn get_data(self) -> Vec<u8> {
-    self.data
+impl fmt::Display for BinaryError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      BinaryError::UnexpectedEof { needed, available: 0, offset } => {
+        write!(f, "Unexpected end of data at offset {}: needed {} bytes, but no bytes were left", offset, needed)
+      }
+      BinaryError::UnexpectedEof { needed, available, offset } => {
+        write!(f, "Unexpected end of data at offset {}: needed {} bytes, but only {} were left", offset, needed, available)
+      }
+      BinaryError::InvalidUtf8 { error, offset } => write!(f, "Invalid UTF-8 string at offset {}: {}", offset, error),
+      BinaryError::InvalidChar { value, offset } => write!(f, "Invalid char value at offset {}: {:#x}", offset, value),
+      BinaryError::Io(kind) => write!(f, "I/O error: {}", kind),
+      BinaryError::LengthOverflow { length, max } => {
+        write!(f, "Length {} exceeds the prefix maximum of {}", length, max)
+      }
+      BinaryError::InvalidOffset { index } => write!(f, "Invalid offset at index {}", index),
+      BinaryError::UnknownVariant(tag) => write!(f, "Unknown enum variant tag: {}", tag),
+      BinaryError::UnknownVariantName(name) => write!(f, "Unknown enum variant name: {}", name),
+      BinaryError::LengthMismatch { left, right } => write!(f, "Slice lengths differ: {} and {}", left, right),
+      BinaryError::SizeLimitExceeded { limit } => write!(f, "Write exceeds the maximum size of {} bytes", limit),
+      BinaryError::UsizeOverflow(v) => write!(f, "Value {} does not fit in usize", v),
+      BinaryError::IsizeOverflow(v) => write!(f, "Value {} does not fit in isize", v),
+      BinaryError::ForeignLengthSlot => write!(f, "Length slot belongs to a different writer"),
+      BinaryError::MagicMismatch { expected, found } => {
+        write!(f, "Expected bytes {:02x?}, found {:02x?}", expected, found)
+      }
+      BinaryError::DuplicateElement { index } => write!(f, "Duplicate set element at index {}", index),
+      BinaryError::UnsupportedVersion { version, min, max } => {
+        write!(f, "Format version {} is not in the supported range {}..={}", version, min, max)
+      }
+      BinaryError::SizeMismatch { expected, found } => write!(f, "Expected {} bytes, found {}", expected, found),
+      BinaryError::InvalidValue(reason) => write!(f, "Invalid value: {}", reason),
+      BinaryError::TrailingBytes { remaining } => write!(f, "{} trailing bytes left unread", remaining),
+      BinaryError::ChecksumMismatch { expected, found } => {
+        write!(f, "Checksum mismatch: footer says {:#010x}, body hashes to {:#010x}", expected, found)
+      }
+    }
   }
+}
 
-  /// Writes a u8 value to the buffer.
-  pub fn write_u8(&mut self, value: u8) {
-    self.data.push(value);
+impl std::error::Error for BinaryError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      BinaryError::InvalidUtf8 { error, .. } => Some(error),
+      _ => None,
+    }
   }
+}
+
+/// Endianness selects the byte order of multi-byte values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endianness {
+  /// Least significant byte first. The default for writers and readers.
+  #[default]
+  Little,
+  /// Most significant byte first, also known as network byte order.
+  Big,
+}
+
+impl Endianness {
+  /// The byte order of the host this code was compiled for.
+  #[cfg(target_endian = "little")]
+  pub const NATIVE: Endianness = Endianness::Little;
+  /// The byte order of the host this code was compiled for.
+  #[cfg(target_endian = "big")]
+  pub const NATIVE: Endianness = Endianness::Big;
+}
+
+/// PrefixWidth selects the integer width used to encode a length prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefixWidth {
+  /// A single byte, for lengths up to 255.
+  U8,
+  /// A u16, for lengths up to 65535.
+  U16,
+  /// A u32, the width used by `write_string` and the vector methods.
+  U32,
+  /// A u64.
+  U64,
+  /// A LEB128 varint, as written by `BinaryWriter::write_varint`: one byte below 128, two below 16384.
+  Varint,
+}
 
-  /// Writes a u16 value to the buffer in little-endian order.
-  pub fn write_u16(&mut self, value: u16) {
-    self.data.extend(&value.to_le_bytes());
+impl PrefixWidth {
+  /// Returns the largest length this prefix can encode.
+  pub fn max_length(self) -> u64 {
+    match self {
+      PrefixWidth::U8 => u8::MAX as u64,
+      PrefixWidth::U16 => u16::MAX as u64,
+      PrefixWidth::U32 => u32::MAX as u64,
+      PrefixWidth::U64 | PrefixWidth::Varint => u64::MAX,
+    }
   }
 
-  /// Writes a u32 value to the buffer in little-endian order.
-  pub fn write_u32(&mut self, value: u32) {
-    self.data.extend(&value.to_le_bytes());
+  /// Returns the fewest bytes this prefix can take: its width, or 1 for a varint.
+  fn min_size(self) -> usize {
+    match self {
+      PrefixWidth::U8 | PrefixWidth::Varint => 1,
+      PrefixWidth::U16 => 2,
+      PrefixWidth::U32 => 4,
+      PrefixWidth::U64 => 8,
+    }
   }
+}
+
+/// ChecksumKind selects the 32-bit checksum of a footer written by `BinaryWriter::finish_with_checksum`
+/// and verified by `BinaryReader::new_verified`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumKind {
+  /// CRC-32 (IEEE 802.3, as used by zip and PNG).
+  Crc32,
+  /// Adler-32 (as used by zlib): faster than CRC-32 but weaker on short inputs.
+  Adler32,
+}
 
-  /// Writes a u64 value to the buffer in little-endian order.
-  pub fn write_u64(&mut self, value: u64) {
-    self.data.extend(&value.to_le_bytes());
+impl ChecksumKind {
+  /// Computes this checksum of `data`.
+  pub fn checksum(self, data: &[u8]) -> u32 {
+    match self {
+      ChecksumKind::Crc32 => !data.iter().fold(!0u32, |crc, &b| CRC32_TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8)),
+      ChecksumKind::Adler32 => {
+        const MOD: u32 = 65521;
+        // 5552 bytes is the longest run whose sums cannot overflow a u32 before reducing.
+        let (mut a, mut b) = (1u32, 0u32);
+        for chunk in data.chunks(5552) {
+          for &byte in chunk {
+            a += byte as u32;
+            b += a;
+          }
+          a %= MOD;
+          b %= MOD;
+        }
+        (b << 16) | a
+      }
+    }
   }
+}
 
-  /// Writes an i8 value to the buffer.
-  pub fn write_i8(&mut self, value: i8) {
-    self.data.push(value as u8);
+/// Lookup table for the reflected CRC-32 polynomial 0xEDB88320, one entry per byte value.
+const CRC32_TABLE: [u32; 256] = {
+  let mut table = [0u32; 256];
+  let mut i = 0;
+  while i < 256 {
+    let mut crc = i as u32;
+    let mut bit = 0;
+    while bit < 8 {
+      crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+      bit += 1;
+    }
+    table[i] = crc;
+    i += 1;
   }
+  table
+};
 
-  /// Writes an i16 value to the buffer in little-endian order.
-  pub fn write_i16(&mut self, value: i16) {
-    self.data.extend(&value.to_le_bytes());
+/// Sink is a destination for the bytes produced by a BinaryWriter.
+pub trait Sink {
+  /// Appends `bytes` to the destination.
+  fn push_bytes(&mut self, bytes: &[u8]) -> Result<(), BinaryError>;
+
+  /// Flushes any buffered bytes to the destination. Called once by `BinaryWriter::finish`.
+  fn flush_bytes(&mut self) -> Result<(), BinaryError> {
+    Ok(())
   }
+}
 
-  /// Writes an i32 value to the buffer in little-endian order.
-  pub fn write_i32(&mut self, value: i32) {
-    self.data.extend(&value.to_le_bytes());
+impl Sink for Vec<u8> {
+  fn push_bytes(&mut self, bytes: &[u8]) -> Result<(), BinaryError> {
+    self.extend_from_slice(bytes);
+    Ok(())
   }
+}
 
-  /// Writes an i64 value to the buffer in little-endian order.
-  pub fn write_i64(&mut self, value: i64) {
-    self.data.extend(&value.to_le_bytes());
+impl Sink for &mut dyn io::Write {
+  fn push_bytes(&mut self, bytes: &[u8]) -> Result<(), BinaryError> {
+    self.write_all(bytes).map_err(|e| BinaryError::Io(e.kind()))
   }
 
-  /// Writes a f32 value to the buffer in little-endian order.
-  pub fn write_f32(&mut self, value: f32) {
-    self.data.extend(&value.to_le_bytes());
+  fn flush_bytes(&mut self) -> Result<(), BinaryError> {
+    self.flush().map_err(|e| BinaryError::Io(e.kind()))
   }
+}
 
-  /// Writes a f64 value to the buffer in little-endian order.
-  pub fn write_f64(&mut self, value: f64) {
-    self.data.extend(&value.to_le_bytes());
+/// Token is one entry in the trace recorded by a writer created with `BinaryWriter::with_trace`.
+/// Its `Display` form reads like `u32(42)` or `str("hi")`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+  U8(u8),
+  U16(u16),
+  U32(u32),
+  U64(u64),
+  I8(i8),
+  I16(i16),
+  I32(i32),
+  I64(i64),
+  Usize(usize),
+  Isize(isize),
+  F32(f32),
+  F64(f64),
+  Bool(bool),
+  Char(char),
+  Str(String),
+  /// A vector of the given length. Its elements are not traced individually.
+  Vec(usize),
+  /// A map with the given number of entries. Its keys and values are not traced individually.
+  Map(usize),
+  /// A run of padding bytes of the given length.
+  Padding(usize),
+}
+
+impl Token {
+  /// Returns the name of the token's type as it appears in its `Display` form, e.g. `"u32"`.
+  pub fn type_name(&self) -> &'static str {
+    match self {
+      Token::U8(_) => "u8",
+      Token::U16(_) => "u16",
+      Token::U32(_) => "u32",
+      Token::U64(_) => "u64",
+      Token::I8(_) => "i8",
+      Token::I16(_) => "i16",
+      Token::I32(_) => "i32",
+      Token::I64(_) => "i64",
+      Token::Usize(_) => "usize",
+      Token::Isize(_) => "isize",
+      Token::F32(_) => "f32",
+      Token::F64(_) => "f64",
+      Token::Bool(_) => "bool",
+      Token::Char(_) => "char",
+      Token::Str(_) => "str",
+      Token::Vec(_) => "vec",
+      Token::Map(_) => "map",
+      Token::Padding(_) => "padding",
+    }
   }
+}
 
-  /// Writes a bool value to the buffer as a single byte (0 or 1).
-  pub fn write_bool(&mut self, value: bool) {
-    self.data.push(if value { 1 } else { 0 });
+impl fmt::Display for Token {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Token::U8(v) => write!(f, "u8({})", v),
+      Token::U16(v) => write!(f, "u16({})", v),
+      Token::U32(v) => write!(f, "u32({})", v),
+      Token::U64(v) => write!(f, "u64({})", v),
+      Token::I8(v) => write!(f, "i8({})", v),
+      Token::I16(v) => write!(f, "i16({})", v),
+      Token::I32(v) => write!(f, "i32({})", v),
+      Token::I64(v) => write!(f, "i64({})", v),
+      Token::Usize(v) => write!(f, "usize({})", v),
+      Token::Isize(v) => write!(f, "isize({})", v),
+      Token::F32(v) => write!(f, "f32({:?})", v),
+      Token::F64(v) => write!(f, "f64({:?})", v),
+      Token::Bool(v) => write!(f, "bool({})", v),
+      Token::Char(v) => write!(f, "char({:?})", v),
+      Token::Str(v) => write!(f, "str({:?})", v),
+      Token::Vec(len) => write!(f, "vec({})", len),
+      Token::Map(len) => write!(f, "map({})", len),
+      Token::Padding(len) => write!(f, "padding({})", len),
+    }
   }
+}
 
-  /// Writes a string to the buffer. First writes the length as u32, then the UTF-8 bytes.
-  pub fn write_string(&mut self, value: &str) {
-    let bytes = value.as_bytes();
-    self.write_u32(bytes.len() as u32);
-    self.data.extend(bytes);
+/// SchemaTrace is the (type name, byte offset) log kept by writers and readers with the
+/// `debug-schema` feature. `nested` is set while a value is being processed, so the values it is
+/// made of (such as a string's length prefix) are not logged separately.
+#[cfg(feature = "debug-schema")]
+#[derive(Debug, Clone, Default)]
+struct SchemaTrace {
+  entries: Vec<(&'static str, usize)>,
+  nested: bool,
+}
+
+/// Source of the ids that tie a `LengthSlot` to the writer that reserved it.
+static NEXT_WRITER_ID: AtomicU64 = AtomicU64::new(0);
+
+/// LengthSlot marks a u32 placeholder written by `BinaryWriter::reserve_length`, to be filled in
+/// with `BinaryWriter::fill_length` once the section that follows it has been written.
+#[derive(Debug)]
+#[must_use = "a reserved length stays zero unless the slot is passed to fill_length"]
+pub struct LengthSlot {
+  writer_id: u64,
+  position: usize,
+}
+
+/// SerializedData is a finished Bin-It buffer, as returned by `BinaryWriter::get_data_typed`. It
+/// derefs to the bytes and converts to and from `Vec<u8>`, but marks in a signature that the blob
+/// is Bin-It-encoded rather than arbitrary bytes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SerializedData(Vec<u8>);
+
+impl SerializedData {
+  /// Returns the underlying bytes.
+  pub fn into_inner(self) -> Vec<u8> {
+    self.0
   }
 
-  /// Writes a vector of u8 to the buffer. First writes the length as u32, then the bytes.
-  pub fn write_vec_u8(&mut self, value: &[u8]) {
-    self.write_u32(value.len() as u32);
-    self.data.extend(value);
+  /// Returns a reader over the bytes.
+  pub fn reader(&self) -> BinaryReader<'_> {
+    BinaryReader::new(&self.0)
   }
+}
 
-  /// Writes a vector of u16 to the buffer. First writes the length as u32, then the bytes in little-endian.
-  pub fn write_vec_u16(&mut self, value: &[u16]) {
-    self.write_u32(value.len() as u32);
-    for &v in value {
-      self.write_u16(v);
-    }
+impl From<Vec<u8>> for SerializedData {
+  fn from(bytes: Vec<u8>) -> Self {
+    SerializedData(bytes)
   }
+}
 
-  /// Writes a vector of u32 to the buffer. First writes the length as u32, then the bytes in little-endian.
-  pub fn write_vec_u32(&mut self, value: &[u32]) {
-    self.write_u32(value.len() as u32);
-    for &v in value {
-      self.write_u32(v);
-    }
+impl From<SerializedData> for Vec<u8> {
+  fn from(data: SerializedData) -> Self {
+    data.0
   }
+}
 
-  /// Writes a vector of u64 to the buffer. First writes the length as u32, then the bytes in little-endian.
-  pub fn write_vec_u64(&mut self, value: &[u64]) {
-    self.write_u32(value.len() as u32);
-    for &v in value {
-      self.write_u64(v);
-    }
+impl AsRef<[u8]> for SerializedData {
+  fn as_ref(&self) -> &[u8] {
+    &self.0
   }
+}
 
-  /// Writes a vector of i8 to the buffer. First writes the length as u32, then the bytes.
-  pub fn write_vec_i8(&mut self, value: &[i8]) {
-    self.write_u32(value.len() as u32);
-    for &v in value {
-      self.write_i8(v);
-    }
+impl Deref for SerializedData {
+  type Target = [u8];
+
+  fn deref(&self) -> &[u8] {
+    &self.0
   }
+}
 
-  /// Writes a vector of i16 to the buffer. First writes the length as u32, then the bytes in little-endian.
-  pub fn write_vec_i16(&mut self, value: &[i16]) {
-    self.write_u32(value.len() as u32);
-    for &v in value {
-      self.write_i16(v);
-    }
+/// BinaryWriter is used to serialize various data types into a byte buffer,
+/// or into any other `Sink` chosen with `BinaryWriter::from_sink`.
+pub struct BinaryWriter<S: Sink = Vec<u8>> {
+  sink: S,
+  endianness: Endianness,
+  written: usize,
+  max_size: Option<usize>,
+  hasher: Option<Box<dyn Hasher + Send + Sync>>,
+  error: Option<BinaryError>,
+  id: u64,
+  trace: Option<Vec<Token>>,
+  canonical_nan: bool,
+  #[cfg(feature = "debug-schema")]
+  schema: SchemaTrace,
+}
+
+impl Default for BinaryWriter {
+  fn default() -> Self {
+    Self::new()
   }
+}
 
-  /// Writes a vector of i32 to the buffer. First writes the length as u32, then the bytes in little-endian.
-  pub fn write_vec_i32(&mut self, value: &[i32]) {
-    self.write_u32(value.len() as u32);
-    for &v in value {
-      self.write_i32(v);
-    }
+impl BinaryWriter {
+  /// Creates a new BinaryWriter with an empty buffer.
+  pub fn new() -> Self {
+    BinaryWriter::from_sink(Vec::new())
   }
 
-  /// Writes a vector of i64 to the buffer. First writes the length as u32, then the bytes in little-endian.
-  pub fn write_vec_i64(&mut self, value: &[i64]) {
-    self.write_u32(value.len() as u32);
-    for &v in value {
-      self.write_i64(v);
-    }
+  /// Creates a new BinaryWriter that appends to `data`, keeping its existing contents (e.g. a header
+  /// written by other code) at the front of the buffer without copying them.
+  pub fn from_vec(data: Vec<u8>) -> Self {
+    let written = data.len();
+    let mut writer = BinaryWriter::from_sink(data);
+    writer.written = written;
+    writer
   }
 
-  /// Writes a vector of f32 to the buffer. First writes the length as u32, then the bytes in little-endian.
-  pub fn write_vec_f32(&mut self, value: &[f32]) {
-    self.write_u32(value.len() as u32);
-    for &v in value {
-      self.write_f32(v);
-    }
+  /// Creates a new BinaryWriter with an empty buffer that can hold `capacity` bytes without
+  /// reallocating, e.g. a total computed with `SizeHint`.
+  pub fn with_capacity(capacity: usize) -> Self {
+    BinaryWriter::from_sink(Vec::with_capacity(capacity))
   }
 
-  /// Writes a vector of f64 to the buffer. First writes the length as u32, then the bytes in little-endian.
-  pub fn write_vec_f64(&mut self, value: &[f64]) {
-    self.write_u32(value.len() as u32);
-    for &v in value {
-      self.write_f64(v);
-    }
+  /// Creates a new BinaryWriter with an empty buffer that writes multi-byte values in the given
+  /// byte order, for formats whose order is only known at runtime (e.g. from a header flag).
+  pub fn new_with_endianness(endianness: Endianness) -> Self {
+    let mut writer = BinaryWriter::new();
+    writer.endianness = endianness;
+    writer
   }
 
-  /// Writes a vector of strings to the buffer. First writes the length as u32, then each string serialized.
-  pub fn write_vec_string(&mut self, value: &[String]) {
-    self.write_u32(value.len() as u32);
-    for s in value {
-      self.write_string(s);
-    }
+  /// Creates a new BinaryWriter whose buffer starts with `version` as a u16, so readers can check it
+  /// with `read_version` or `require_version` before decoding the rest.
+  pub fn new_versioned(version: u16) -> Self {
+    let mut writer = BinaryWriter::new();
+    writer.write_u16(version);
+    writer
   }
-}
 
-/// BinaryReader is used to deserialize various data types from a byte buffer.
-pub struct BinaryReader<'a> {
-  data: &'a [u8],
-  cursor: usize,
-}
+  /// Creates a new BinaryWriter that feeds every written byte into `hasher` as it goes, so the hash
+  /// of the payload is available without a second pass. The hasher sees the buffer as a sequence of
+  /// `Hasher::write` calls, one per chunk written. Hashers that are insensitive to how their input is
+  /// split (e.g. `DefaultHasher`) thus match hashing the final buffer in one call; others may not.
+  pub fn with_hasher<H: Hasher + Send + Sync + 'static>(hasher: H) -> Self {
+    let mut writer = BinaryWriter::new();
+    writer.hasher = Some(Box::new(hasher));
+    writer
+  }
 
-impl<'a> BinaryReader<'a> {
-  /// Creates a new BinaryReader with the given byte slice.
-  pub fn new(data: &'a [u8]) -> Self {
-    BinaryReader { data, cursor: 0 }
+  /// Creates a new BinaryWriter whose buffer may not grow beyond `limit` bytes. A write that would
+  /// exceed the limit is dropped and fails the writer: `try_write` reports it immediately, and
+  /// `finish` returns `BinaryError::SizeLimitExceeded`.
+  pub fn with_max_size(limit: usize) -> Self {
+    let mut writer = BinaryWriter::new();
+    writer.max_size = Some(limit);
+    writer
   }
 
-  /// Reads a u8 value from the buffer.
-  pub fn read_u8(&mut self) -> Result<u8, String> {
-    self.ensure_available(1)?;
-    let value = self.data[self.cursor];
-    self.cursor += 1;
-    Ok(value)
+  /// Returns a reference to the internal byte buffer.
+  pub fn get_data(self) -> Vec<u8> {
+    self.sink
   }
 
-  /// Reads a u16 value from the buffer in little-endian order.
-  pub fn read_u16(&mut self) -> Result<u16, String> {
-    self.ensure_available(2)?;
-    let bytes = &self.data[self.cursor..self.cursor + 2];
-    self.cursor += 2;
-    Ok(u16::from_le_bytes(bytes.try_into().unwrap()))
+  /// Returns the internal byte buffer wrapped as `SerializedData`.
+  pub fn get_data_typed(self) -> SerializedData {
+    SerializedData(self.sink)
   }
 
-  /// Reads a u32 value from the buffer in little-endian order.
-  pub fn read_u32(&mut self) -> Result<u32, String> {
-    self.ensure_available(4)?;
-    let bytes = &self.data[self.cursor..self.cursor + 4];
-    self.cursor += 4;
-    Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+  /// Returns the internal byte buffer together with its accumulated hash (if a hasher was set).
+  pub fn get_data_with_hash(self) -> (Vec<u8>, Option<u64>) {
+    let hash = self.hash();
+    (self.sink, hash)
   }
 
-  /// Reads a u64 value from the buffer in little-endian order.
-  pub fn read_u64(&mut self) -> Result<u64, String> {
-    self.ensure_available(8)?;
-    let bytes = &self.data[self.cursor..self.cursor + 8];
-    self.cursor += 8;
-    Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+  /// Creates a new BinaryWriter that also records a `Token` for each value written, for debugging
+  /// and snapshot tests. The trace is kept in memory alongside the bytes, so leave it off otherwise.
+  pub fn with_trace() -> Self {
+    let mut writer = BinaryWriter::new();
+    writer.trace = Some(Vec::new());
+    writer
   }
 
-  /// Reads an i8 value from the buffer.
-  pub fn read_i8(&mut self) -> Result<i8, String> {
-    self.ensure_available(1)?;
-    let value = self.data[self.cursor] as i8;
-    self.cursor += 1;
-    Ok(value)
+  /// Writes a zero u32 placeholder for the length of the section that follows, returning a slot
+  /// to pass to `fill_length` once the section is written. Saves buffering the section separately
+  /// just to learn its size. A hasher sees the placeholder, not the filled-in length.
+  pub fn reserve_length(&mut self) -> LengthSlot {
+    let position = self.sink.len();
+    self.write_u32(0);
+    LengthSlot { writer_id: self.id, position }
   }
 
-  /// Reads an i16 value from the buffer in little-endian order.
-  pub fn read_i16(&mut self) -> Result<i16, String> {
-    self.read_u16().map(|v| v as i16)
+  /// Overwrites the placeholder reserved by `slot` with the number of bytes written after it, as a
+  /// u32 in the writer's byte order. Errors if the slot came from another writer or the section is
+  /// longer than `u32::MAX` bytes.
+  pub fn fill_length(&mut self, slot: LengthSlot) -> Result<&mut Self, BinaryError> {
+    if slot.writer_id != self.id {
+      return Err(BinaryError::ForeignLengthSlot);
+    }
+    let start = slot.position + 4;
+    if start > self.sink.len() {
+      // The placeholder itself was dropped, so the writer has already failed.
+      return Err(self.error.clone().unwrap_or(BinaryError::ForeignLengthSlot));
+    }
+    let length = self.sink.len() - start;
+    let value = u32::try_from(length).map_err(|_| BinaryError::LengthOverflow { length, max: u32::MAX as u64 })?;
+    let bytes = match self.endianness {
+      Endianness::Little => value.to_le_bytes(),
+      Endianness::Big => value.to_be_bytes(),
+    };
+    self.sink[slot.position..start].copy_from_slice(&bytes);
+    Ok(self)
   }
 
-  /// Reads an i32 value from the buffer in little-endian order.
-  pub fn read_i32(&mut self) -> Result<i32, String> {
-    self.read_u32().map(|v| v as i32)
+  /// Finalizes the writer like `finish` and returns its buffer prefixed with the payload's byte count
+  /// as a u32, for framed protocols. Read it back with `BinaryReader::read_frame`.
+  pub fn finish_framed(self) -> Result<Vec<u8>, BinaryError> {
+    self.finish_framed_with_prefix(PrefixWidth::U32)
   }
 
-  /// Reads an i64 value from the buffer in little-endian order.
-  pub fn read_i64(&mut self) -> Result<i64, String> {
-    self.read_u64().map(|v| v as i64)
+  /// Like `finish_framed`, with a byte count prefix of the given width in the writer's byte order.
+  /// Errors if the payload is too long for the prefix.
+  pub fn finish_framed_with_prefix(self, prefix: PrefixWidth) -> Result<Vec<u8>, BinaryError> {
+    let endianness = self.endianness;
+    let payload = self.finish()?;
+    let mut framed = BinaryWriter::new_with_endianness(endianness);
+    framed.write_length(payload.len(), prefix)?;
+    framed.push_bytes(&payload);
+    framed.finish()
   }
 
-  /// Reads a f32 value from the buffer in little-endian order.
-  pub fn read_f32(&mut self) -> Result<f32, String> {
-    self.ensure_available(4)?;
-    let bytes = &self.data[self.cursor..self.cursor + 4];
-    self.cursor += 4;
-    Ok(f32::from_le_bytes(bytes.try_into().unwrap()))
+  /// Finalizes the writer like `finish` and returns its buffer preceded by the strings collected in
+  /// `interner`, as a packed string table. Read the table back with `BinaryReader::read_string_table`
+  /// before decoding the rest, and resolve each `write_interned` index with `read_interned`.
+  pub fn finish_interned(self, interner: &StringInterner) -> Result<Vec<u8>, BinaryError> {
+    let endianness = self.endianness;
+    let payload = self.finish()?;
+    let mut table = BinaryWriter::new_with_endianness(endianness);
+    table.write_vec_string_packed(&interner.strings);
+    table.push_bytes(&payload);
+    table.finish()
   }
 
-  /// Reads a f64 value from the buffer in little-endian order.
-  pub fn read_f64(&mut self) -> Result<f64, String> {
-    self.ensure_available(8)?;
-    let bytes = &self.data[self.cursor..self.cursor + 8];
-    self.cursor += 8;
-    Ok(f64::from_le_bytes(bytes.try_into().unwrap()))
+  /// Finalizes the writer like `finish` and returns its buffer followed by a footer holding the
+  /// chosen checksum of it, as a little-endian u32. Open the result with `BinaryReader::new_verified`.
+  pub fn finish_with_checksum(self, kind: ChecksumKind) -> Result<Vec<u8>, BinaryError> {
+    let mut data = self.finish()?;
+    let checksum = kind.checksum(&data);
+    data.extend_from_slice(&checksum.to_le_bytes());
+    Ok(data)
   }
 
-  /// Reads a bool value from the buffer (expects 0 or 1).
-  pub fn read_bool(&mut self) -> Result<bool, String> {
-    self.read_u8().map(|v| match v {
-      0 => false,
-      1 => true,
-      _ => panic!("Invalid boolean value: {}", v),
-    })
+  /// Shrinks the buffer back to `len` bytes, undoing everything written since `len()` returned that
+  /// length, e.g. to roll back a speculative write. Does nothing if the buffer is not longer than
+  /// `len`. Traces, hashes and any error already recorded are not rewound.
+  pub fn truncate(&mut self, len: usize) -> &mut Self {
+    self.sink.truncate(len);
+    self.written = self.sink.len();
+    self
   }
 
-  /// Reads a string from the buffer. Expects a u32 length followed by UTF-8 bytes.
-  pub fn read_string(&mut self) -> Result<String, String> {
-    let length = self.read_u32()? as usize;
-    self.ensure_available(length)?;
-    let bytes = &self.data[self.cursor..self.cursor + length];
-    self.cursor += length;
-    String::from_utf8(bytes.to_vec()).map_err(|e| e.to_string())
+  /// Returns the bytes written so far as a lowercase hex string with no separators.
+  pub fn to_hex_string(&self) -> String {
+    self.sink.iter().map(|b| format!("{:02x}", b)).collect()
   }
+}
 
-  /// Reads a vector of u8 from the buffer. Expects a u32 length followed by bytes.
-  pub fn read_vec_u8(&mut self) -> Result<Vec<u8>, String> {
-    let length = self.read_u32()? as usize;
-    self.ensure_available(length)?;
-    let vec = self.data[self.cursor..self.cursor + length].to_vec();
-    self.cursor += length;
-    Ok(vec)
+impl fmt::Debug for BinaryWriter {
+  /// Prints the buffer length followed by a hex dump of its contents.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "BinaryWriter ({} bytes)", self.sink.len())?;
+    write!(f, "{}", hex_dump(&self.sink))
   }
+}
 
-  /// Reads a vector of u16 from the buffer. Expects a u32 length followed by u16 values.
-  pub fn read_vec_u16(&mut self) -> Result<Vec<u16>, String> {
-    let length = self.read_u32()? as usize;
-    let mut vec = Vec::with_capacity(length);
-    for _ in 0..length {
-      vec.push(self.read_u16()?);
+impl<S: Sink> BinaryWriter<S> {
+  /// Creates a new BinaryWriter that writes into `sink`. Sink errors do not interrupt writing;
+  /// the first one is kept, later writes are dropped, and the error is returned by `finish`.
+  pub fn from_sink(sink: S) -> Self {
+    BinaryWriter {
+      sink,
+      endianness: Endianness::Little,
+      written: 0,
+      max_size: None,
+      hasher: None,
+      error: None,
+      id: NEXT_WRITER_ID.fetch_add(1, Ordering::Relaxed),
+      trace: None,
+      canonical_nan: false,
+      #[cfg(feature = "debug-schema")]
+      schema: SchemaTrace::default(),
     }
-    Ok(vec)
   }
 
-  /// Reads a vector of u32 from the buffer. Expects a u32 length followed by u32 values.
-  pub fn read_vec_u32(&mut self) -> Result<Vec<u32>, String> {
-    let length = self.read_u32()? as usize;
-    let mut vec = Vec::with_capacity(length);
-    for _ in 0..length {
-      vec.push(self.read_u32()?);
-    }
-    Ok(vec)
+  /// Returns the byte order used for multi-byte values.
+  pub fn endianness(&self) -> Endianness {
+    self.endianness
   }
 
-  /// Reads a vector of u64 from the buffer. Expects a u32 length followed by u64 values.
-  pub fn read_vec_u64(&mut self) -> Result<Vec<u64>, String> {
-    let length = self.read_u32()? as usize;
-    let mut vec = Vec::with_capacity(length);
-    for _ in 0..length {
-      vec.push(self.read_u64()?);
-    }
-    Ok(vec)
+  /// Sets the byte order used for multi-byte values, including length prefixes.
+  pub fn set_endianness(&mut self, endianness: Endianness) -> &mut Self {
+    self.endianness = endianness;
+    self
   }
 
-  /// Reads a vector of i8 from the buffer. Expects a u32 length followed by i8 values.
-  pub fn read_vec_i8(&mut self) -> Result<Vec<i8>, String> {
-    let length = self.read_u32()? as usize;
-    let mut vec = Vec::with_capacity(length);
-    for _ in 0..length {
-      vec.push(self.read_i8()?);
+  /// Finalizes the writer and returns its sink (the byte buffer for in-memory writers). The in-memory
+  /// writer never fails, but sinks that flush, compress or checksum report their errors here, as does
+  /// any error a sink returned while writing.
+  pub fn finish(mut self) -> Result<S, BinaryError> {
+    if let Some(e) = self.error.take() {
+      return Err(e);
     }
-    Ok(vec)
+    self.sink.flush_bytes()?;
+    Ok(self.sink)
   }
 
-  /// Reads a vector of i16 from the buffer. Expects a u32 length followed by i16 values.
-  pub fn read_vec_i16(&mut self) -> Result<Vec<i16>, String> {
-    let length = self.read_u32()? as usize;
-    let mut vec = Vec::with_capacity(length);
-    for _ in 0..length {
-      vec.push(self.read_i16()?);
+  /// Writes any value implementing `Writable`, returning an error if this or an earlier write
+  /// failed, e.g. because it exceeded the limit set with `with_max_size`.
+  pub fn try_write<T: Writable + ?Sized>(&mut self, value: &T) -> Result<&mut Self, BinaryError> {
+    self.write(value);
+    match &self.error {
+      Some(e) => Err(e.clone()),
+      None => Ok(self),
     }
-    Ok(vec)
   }
 
-  /// Reads a vector of i32 from the buffer. Expects a u32 length followed by i32 values.
-  pub fn read_vec_i32(&mut self) -> Result<Vec<i32>, String> {
-    let length = self.read_u32()? as usize;
-    let mut vec = Vec::with_capacity(length);
-    for _ in 0..length {
-      vec.push(self.read_i32()?);
-    }
-    Ok(vec)
+  /// Enables or disables NaN canonicalization. When enabled, the float write methods replace every
+  /// NaN with a single quiet NaN bit pattern, so logically equal data always produces identical bytes
+  /// (e.g. for content-addressed storage). Other values are unaffected, and `write_f32_bits` and
+  /// `write_f64_bits` always write their bits verbatim.
+  pub fn canonicalize_nan(&mut self, enabled: bool) -> &mut Self {
+    self.canonical_nan = enabled;
+    self
   }
 
-  /// Reads a vector of i64 from the buffer. Expects a u32 length followed by i64 values.
-  pub fn read_vec_i64(&mut self) -> Result<Vec<i64>, String> {
-    let length = self.read_u32()? as usize;
-    let mut vec = Vec::with_capacity(length);
-    for _ in 0..length {
-      vec.push(self.read_i64()?);
-    }
-    Ok(vec)
+  /// Returns the (type name, byte offset) of each value written so far, in order. Compare it with a
+  /// reader's `BinaryReader::schema_trace` to find the first field where the two sides disagree.
+  #[cfg(feature = "debug-schema")]
+  pub fn schema_trace(&self) -> Vec<(&'static str, usize)> {
+    self.schema.entries.clone()
   }
 
-  /// Reads a vector of f32 from the buffer. Expects a u32 length followed by f32 values.
-  pub fn read_vec_f32(&mut self) -> Result<Vec<f32>, String> {
-    let length = self.read_u32()? as usize;
-    let mut vec = Vec::with_capacity(length);
-    for _ in 0..length {
-      vec.push(self.read_f32()?);
-    }
-    Ok(vec)
+  /// Returns the tokens recorded so far by a writer created with `with_trace`, or an empty slice.
+  pub fn trace(&self) -> &[Token] {
+    self.trace.as_deref().unwrap_or(&[])
   }
 
-  /// Reads a vector of f64 from the buffer. Expects a u32 length followed by f64 values.
-  pub fn read_vec_f64(&mut self) -> Result<Vec<f64>, String> {
-    let length = self.read_u32()? as usize;
-    let mut vec = Vec::with_capacity(length);
-    for _ in 0..length {
-      vec.push(self.read_f64()?);
-    }
-    Ok(vec)
+  /// Returns the hash of the bytes written so far, or `None` if the writer has no hasher.
+  pub fn hash(&self) -> Option<u64> {
+    self.hasher.as_ref().map(|h| h.finish())
   }
 
-  /// Reads a vector of strings from the buffer. Expects a u32 length followed by serialized strings.
-  pub fn read_vec_string(&mut self) -> Result<Vec<String>, String> {
-    let length = self.read_u32()? as usize;
-    let mut vec = Vec::with_capacity(length);
-    for _ in 0..length {
-      vec.push(self.read_string()?);
-    }
-    Ok(vec)
+  /// Returns the number of bytes written so far, including any contents the buffer started with.
+  pub fn len(&self) -> usize {
+    self.written
   }
 
-  /// Ensures that there are at least `size` bytes available to read.
-  fn ensure_available(&self, size: usize) -> Result<(), String> {
-    if self.cursor + size > self.data.len() {
-      Err("Unexpected end of data".to_string())
-    } else {
-      Ok(())
-    }
+  /// Returns true if nothing has been written.
+  pub fn is_empty(&self) -> bool {
+    self.written == 0
   }
-}
 
-#[cfg(test)]
-mod tests {
-  use super::*;
+  /// Writes a u8 value to the buffer.
+  pub fn write_u8(&mut self, value: u8) -> &mut Self {
+    self.traced(|| Token::U8(value), |w| w.push_bytes(&[value]))
+  }
 
-  #[test]
-  fn test_binary_writer_reader() {
-    let mut writer = BinaryWriter::new();
+  /// Writes a u16 value to the buffer in the writer's byte order (little-endian by default).
+  pub fn write_u16(&mut self, value: u16) -> &mut Self {
+    self.traced(|| Token::U16(value), |w| w.push_ordered(value.to_le_bytes()))
+  }
 
-    // Write various data types
-    writer.write_u8(255);
-    writer.write_i8(-128);
-    writer.write_u16(65535);
-    writer.write_i16(-32768);
-    writer.write_u32(4294967295);
-    writer.write_i32(-2147483648);
-    writer.write_u64(18446744073709551615);
-    writer.write_i64(-9223372036854775808);
-    writer.write_f32(3.1415927);
-    writer.write_f64(2.718281828459045);
-    writer.write_bool(true);
-    writer.write_string("Hello, World!");
+  /// Writes a u32 value to the buffer in the writer's byte order (little-endian by default).
+  pub fn write_u32(&mut self, value: u32) -> &mut Self {
+    self.traced(|| Token::U32(value), |w| w.push_ordered(value.to_le_bytes()))
+  }
 
-    let data = writer.get_data().clone();
+  /// Writes a u64 value to the buffer in the writer's byte order (little-endian by default).
+  pub fn write_u64(&mut self, value: u64) -> &mut Self {
+    self.traced(|| Token::U64(value), |w| w.push_ordered(value.to_le_bytes()))
+  }
 
-    let mut reader = BinaryReader::new(&data);
+  /// Writes the low 3 bytes of a u32 in the writer's byte order (little-endian by default), e.g. a
+  /// 24-bit audio sample. Values are never masked: one above `0xFF_FFFF` is an `InvalidValue` error
+  /// and nothing is written.
+  pub fn write_u24(&mut self, value: u32) -> Result<&mut Self, BinaryError> {
+    check_u24(value)?;
+    Ok(self.traced(|| Token::U32(value), |w| w.push_u24(value)))
+  }
 
-    // Read and assert the values
-    assert_eq!(reader.read_u8().unwrap(), 255);
-    assert_eq!(reader.read_i8().unwrap(), -128);
-    assert_eq!(reader.read_u16().unwrap(), 65535);
-    assert_eq!(reader.read_i16().unwrap(), -32768);
-    assert_eq!(reader.read_u32().unwrap(), 4294967295);
-    assert_eq!(reader.read_i32().unwrap(), -2147483648);
-    assert_eq!(reader.read_u64().unwrap(), 18446744073709551615);
-    assert_eq!(reader.read_i64().unwrap(), -9223372036854775808);
-    assert!((reader.read_f32().unwrap() - 3.1415927).abs() < 1e-6);
-    assert!((reader.read_f64().unwrap() - 2.718281828459045).abs() < 1e-12);
-    assert_eq!(reader.read_bool().unwrap(), true);
-    assert_eq!(reader.read_string().unwrap(), "Hello, World!");
+  /// Writes an i32 in 3 bytes (two's complement, in the writer's byte order). Values outside
+  /// `-0x80_0000..=0x7F_FFFF` are an `InvalidValue` error rather than being truncated, and nothing
+  /// is written.
+  pub fn write_i24(&mut self, value: i32) -> Result<&mut Self, BinaryError> {
+    check_i24(value)?;
+    Ok(self.traced(|| Token::I32(value), |w| w.push_u24(value as u32)))
   }
 
-  #[test]
-  fn test_binary_writer_reader_vectors() {
-    let mut writer = BinaryWriter::new();
+  /// Writes an i8 value to the buffer.
+  pub fn write_i8(&mut self, value: i8) -> &mut Self {
+    self.traced(|| Token::I8(value), |w| w.push_bytes(&[value as u8]))
+  }
 
-    // Write vectors
-    writer.write_vec_u8(&[1, 2, 3, 4, 5]);
-    writer.write_vec_i16(&[-1, -2, -3]);
-    writer.write_vec_f64(&[1.1, 2.2, 3.3]);
+  /// Writes an i16 value to the buffer in the writer's byte order (little-endian by default).
+  pub fn write_i16(&mut self, value: i16) -> &mut Self {
+    self.traced(|| Token::I16(value), |w| w.push_ordered(value.to_le_bytes()))
+  }
 
-    let data = writer.get_data().clone();
+  /// Writes an i32 value to the buffer in the writer's byte order (little-endian by default).
+  pub fn write_i32(&mut self, value: i32) -> &mut Self {
+    self.traced(|| Token::I32(value), |w| w.push_ordered(value.to_le_bytes()))
+  }
 
-    let mut reader = BinaryReader::new(&data);
+  /// Writes an i64 value to the buffer in the writer's byte order (little-endian by default).
+  pub fn write_i64(&mut self, value: i64) -> &mut Self {
+    self.traced(|| Token::I64(value), |w| w.push_ordered(value.to_le_bytes()))
+  }
 
-    // Read and assert the vectors
-    assert_eq!(reader.read_vec_u8().unwrap(), vec![1, 2, 3, 4, 5]);
-    assert_eq!(reader.read_vec_i16().unwrap(), vec![-1, -2, -3]);
-    let read_f64 = reader.read_vec_f64().unwrap();
-    assert_eq!(read_f64.len(), 3);
-    assert!((read_f64[0] - 1.1).abs() < 1e-10);
-    assert!((read_f64[1] - 2.2).abs() < 1e-10);
-    assert!((read_f64[2] - 3.3).abs() < 1e-10);
+  /// Writes a u64 as an unsigned LEB128 varint: 7 bits per byte, low groups first, with the high
+  /// bit set on every byte but the last. Values below 128 take one byte, and none takes more than 10.
+  /// The encoding is the same in either byte order.
+  pub fn write_varint(&mut self, value: u64) -> &mut Self {
+    self.traced(|| Token::U64(value), |w| {
+      let mut rest = value;
+      while rest >= 0x80 {
+        w.push_bytes(&[rest as u8 | 0x80]);
+        rest >>= 7;
+      }
+      w.push_bytes(&[rest as u8]);
+    })
   }
 
-  #[test]
-  fn test_binary_writer_reader_vec_string() {
-    let mut writer = BinaryWriter::new();
+  /// Writes a usize value to the buffer. Always written as a u64 so the wire format
+  /// is 64-bit regardless of the target's pointer width.
+  pub fn write_usize(&mut self, value: usize) -> &mut Self {
+    self.traced(|| Token::Usize(value), |w| {
+      w.write_u64(value as u64);
+    })
+  }
 
-    // Write a vector of strings
-    let strings = vec![
-      "Hello".to_string(),
-      "Bin-It".to_string(),
-      "Serialization".to_string(),
-      "".to_string(),
-      "🚀✨".to_string(),
-    ];
-    writer.write_vec_string(&strings);
+  /// Writes an isize value to the buffer. Always written as an i64 so the wire format
+  /// is 64-bit regardless of the target's pointer width.
+  pub fn write_isize(&mut self, value: isize) -> &mut Self {
+    self.traced(|| Token::Isize(value), |w| {
+      w.write_i64(value as i64);
+    })
+  }
 
-    let data = writer.get_data().clone();
+  /// Writes a f32 value to the buffer in the writer's byte order (little-endian by default).
+  pub fn write_f32(&mut self, value: f32) -> &mut Self {
+    let value = self.canonical_f32(value);
+    self.traced(|| Token::F32(value), |w| w.push_ordered(value.to_le_bytes()))
+  }
 
-    let mut reader = BinaryReader::new(&data);
+  /// Writes a f64 value to the buffer in the writer's byte order (little-endian by default).
+  pub fn write_f64(&mut self, value: f64) -> &mut Self {
+    let value = self.canonical_f64(value);
+    self.traced(|| Token::F64(value), |w| w.push_ordered(value.to_le_bytes()))
+  }
 
-    // Read and assert the vector of strings
-    let read_strings = reader.read_vec_string().unwrap();
-    assert_eq!(read_strings, strings);
+  /// Writes the raw bit pattern of a f32 to the buffer in the writer's byte order. The bits are written
+  /// verbatim, so arbitrary NaN payloads (including signaling NaNs) are preserved exactly.
+  pub fn write_f32_bits(&mut self, bits: u32) -> &mut Self {
+    self.traced(|| Token::F32(f32::from_bits(bits)), |w| {
+      w.write_u32(bits);
+    })
   }
 
-  #[test]
-  fn test_binary_reader_error() {
-    let data = vec![1, 2]; // Insufficient data for a u32
+  /// Writes the raw bit pattern of a f64 to the buffer in the writer's byte order. The bits are written
+  /// verbatim, so arbitrary NaN payloads (including signaling NaNs) are preserved exactly.
+  pub fn write_f64_bits(&mut self, bits: u64) -> &mut Self {
+    self.traced(|| Token::F64(f64::from_bits(bits)), |w| {
+      w.write_u64(bits);
+    })
+  }
 
-    let mut reader = BinaryReader::new(&data);
+  /// Writes a u16 value to the buffer in little-endian order, regardless of the default byte order.
+  pub fn write_u16_le(&mut self, value: u16) -> &mut Self {
+    self.traced(|| Token::U16(value), |w| w.push_bytes(&value.to_le_bytes()))
+  }
 
-    // Attempt to read a u32, which should fail
-    assert!(reader.read_u32().is_err());
+  /// Writes a u32 value to the buffer in little-endian order, regardless of the default byte order.
+  pub fn write_u32_le(&mut self, value: u32) -> &mut Self {
+    self.traced(|| Token::U32(value), |w| w.push_bytes(&value.to_le_bytes()))
+  }
+
+  /// Writes a u64 value to the buffer in little-endian order, regardless of the default byte order.
+  pub fn write_u64_le(&mut self, value: u64) -> &mut Self {
+    self.traced(|| Token::U64(value), |w| w.push_bytes(&value.to_le_bytes()))
+  }
+
+  /// Writes an i16 value to the buffer in little-endian order, regardless of the default byte order.
+  pub fn write_i16_le(&mut self, value: i16) -> &mut Self {
+    self.traced(|| Token::I16(value), |w| w.push_bytes(&value.to_le_bytes()))
+  }
+
+  /// Writes an i32 value to the buffer in little-endian order, regardless of the default byte order.
+  pub fn write_i32_le(&mut self, value: i32) -> &mut Self {
+    self.traced(|| Token::I32(value), |w| w.push_bytes(&value.to_le_bytes()))
+  }
+
+  /// Writes an i64 value to the buffer in little-endian order, regardless of the default byte order.
+  pub fn write_i64_le(&mut self, value: i64) -> &mut Self {
+    self.traced(|| Token::I64(value), |w| w.push_bytes(&value.to_le_bytes()))
+  }
+
+  /// Writes a f32 value to the buffer in little-endian order, regardless of the default byte order.
+  pub fn write_f32_le(&mut self, value: f32) -> &mut Self {
+    let value = self.canonical_f32(value);
+    self.traced(|| Token::F32(value), |w| w.push_bytes(&value.to_le_bytes()))
+  }
+
+  /// Writes a f64 value to the buffer in little-endian order, regardless of the default byte order.
+  pub fn write_f64_le(&mut self, value: f64) -> &mut Self {
+    let value = self.canonical_f64(value);
+    self.traced(|| Token::F64(value), |w| w.push_bytes(&value.to_le_bytes()))
+  }
+
+  /// Writes a u16 value to the buffer in big-endian order, regardless of the default byte order.
+  pub fn write_u16_be(&mut self, value: u16) -> &mut Self {
+    self.traced(|| Token::U16(value), |w| w.push_bytes(&value.to_be_bytes()))
+  }
+
+  /// Writes a u32 value to the buffer in big-endian order, regardless of the default byte order.
+  pub fn write_u32_be(&mut self, value: u32) -> &mut Self {
+    self.traced(|| Token::U32(value), |w| w.push_bytes(&value.to_be_bytes()))
+  }
+
+  /// Writes a u64 value to the buffer in big-endian order, regardless of the default byte order.
+  pub fn write_u64_be(&mut self, value: u64) -> &mut Self {
+    self.traced(|| Token::U64(value), |w| w.push_bytes(&value.to_be_bytes()))
+  }
+
+  /// Writes an i16 value to the buffer in big-endian order, regardless of the default byte order.
+  pub fn write_i16_be(&mut self, value: i16) -> &mut Self {
+    self.traced(|| Token::I16(value), |w| w.push_bytes(&value.to_be_bytes()))
+  }
+
+  /// Writes an i32 value to the buffer in big-endian order, regardless of the default byte order.
+  pub fn write_i32_be(&mut self, value: i32) -> &mut Self {
+    self.traced(|| Token::I32(value), |w| w.push_bytes(&value.to_be_bytes()))
+  }
+
+  /// Writes an i64 value to the buffer in big-endian order, regardless of the default byte order.
+  pub fn write_i64_be(&mut self, value: i64) -> &mut Self {
+    self.traced(|| Token::I64(value), |w| w.push_bytes(&value.to_be_bytes()))
+  }
+
+  /// Writes a f32 value to the buffer in big-endian order, regardless of the default byte order.
+  pub fn write_f32_be(&mut self, value: f32) -> &mut Self {
+    let value = self.canonical_f32(value);
+    self.traced(|| Token::F32(value), |w| w.push_bytes(&value.to_be_bytes()))
+  }
+
+  /// Writes a f64 value to the buffer in big-endian order, regardless of the default byte order.
+  pub fn write_f64_be(&mut self, value: f64) -> &mut Self {
+    let value = self.canonical_f64(value);
+    self.traced(|| Token::F64(value), |w| w.push_bytes(&value.to_be_bytes()))
+  }
+
+  /// Writes a bool value to the buffer as a single byte (0 or 1).
+  pub fn write_bool(&mut self, value: bool) -> &mut Self {
+    self.traced(|| Token::Bool(value), |w| w.push_bytes(&[if value { 1 } else { 0 }]))
+  }
+
+  /// Writes a char to the buffer as its u32 code point in the writer's byte order (little-endian by default).
+  pub fn write_char(&mut self, value: char) -> &mut Self {
+    self.traced(|| Token::Char(value), |w| {
+      w.write_u32(value as u32);
+    })
+  }
+
+  /// Writes a string to the buffer. First writes the length as u32, then the UTF-8 bytes. A string
+  /// longer than `u32::MAX` bytes fails the writer with `LengthOverflow`, reported by `finish`, rather
+  /// than writing a truncated length; the vector methods do the same for oversized counts. Use
+  /// `try_write_string` to get the error straight away.
+  pub fn write_string(&mut self, value: &str) -> &mut Self {
+    self.traced(|| Token::Str(value.to_string()), |w| {
+      let bytes = value.as_bytes();
+      w.write_count(bytes.len());
+      w.push_bytes(bytes);
+    })
+  }
+
+  /// Writes a string like `write_string`, but returns `LengthOverflow` without writing anything if it
+  /// is longer than `u32::MAX` bytes.
+  pub fn try_write_string(&mut self, value: &str) -> Result<&mut Self, BinaryError> {
+    self.write_string_with_prefix(value, PrefixWidth::U32)
+  }
+
+  /// Writes a string to the buffer with a length prefix of the given width, then the UTF-8 bytes.
+  /// Errors without writing anything if the string is too long for the prefix.
+  pub fn write_string_with_prefix(&mut self, value: &str, prefix: PrefixWidth) -> Result<&mut Self, BinaryError> {
+    let bytes = value.as_bytes();
+    if bytes.len() as u64 > prefix.max_length() {
+      return Err(BinaryError::LengthOverflow { length: bytes.len(), max: prefix.max_length() });
+    }
+    Ok(self.traced(|| Token::Str(value.to_string()), |w| {
+      // The length was checked above, so this cannot fail.
+      let _ = w.write_length(bytes.len(), prefix);
+      w.push_bytes(bytes);
+    }))
+  }
+
+  /// Writes a string as UTF-16LE, e.g. for Windows formats: first the number of u16 code units as a
+  /// u32, then the code units, both always little-endian. Read it back with `read_string_utf16`, not
+  /// `read_string`.
+  pub fn write_string_utf16(&mut self, value: &str) -> &mut Self {
+    self.traced(|| Token::Str(value.to_string()), |w| {
+      let units: Vec<u16> = value.encode_utf16().collect();
+      match u32::try_from(units.len()) {
+        Ok(count) => {
+          w.write_u32_le(count);
+        }
+        // Latches `LengthOverflow` without writing anything.
+        Err(_) => w.write_count(units.len()),
+      }
+      for unit in units {
+        w.push_bytes(&unit.to_le_bytes());
+      }
+    })
+  }
+
+  /// Writes a string with a LEB128 varint length prefix (as `write_varint` writes it), the most
+  /// compact choice for typical strings: one byte of prefix below 128 bytes, two below 16384.
+  pub fn write_string_varint(&mut self, value: &str) -> &mut Self {
+    // A varint holds any length, so this cannot fail.
+    let _ = self.write_string_with_prefix(value, PrefixWidth::Varint);
+    self
+  }
+
+  /// Writes a borrowed or owned string to the buffer, encoded exactly like `write_string`.
+  #[allow(clippy::ptr_arg)] // Taking `&Cow` lets callers pass their field as-is.
+  pub fn write_cow_str(&mut self, value: &Cow<'_, str>) -> &mut Self {
+    self.write_string(value)
+  }
+
+  /// Writes a vector of u8 to the buffer. First writes the length as u32, then the bytes.
+  pub fn write_vec_u8(&mut self, value: &[u8]) -> &mut Self {
+    self.traced(|| Token::Vec(value.len()), |w| {
+      w.write_count(value.len());
+      w.push_bytes(value);
+    })
+  }
+
+  /// Writes anything viewable as bytes (`Vec<u8>`, `[u8; N]`, `&str`, ...) exactly like
+  /// `write_vec_u8`: the length as u32, then the bytes.
+  pub fn write_bytes<B: AsRef<[u8]>>(&mut self, bytes: B) -> &mut Self {
+    self.write_vec_u8(bytes.as_ref())
+  }
+
+  /// Writes a short byte blob with a single u8 length prefix, then the bytes. Errors without writing
+  /// anything if there are more than 255 bytes.
+  pub fn write_bytes_u8len(&mut self, bytes: &[u8]) -> Result<&mut Self, BinaryError> {
+    let length = u8::try_from(bytes.len())
+      .map_err(|_| BinaryError::LengthOverflow { length: bytes.len(), max: PrefixWidth::U8.max_length() })?;
+    Ok(self.traced(|| Token::Vec(bytes.len()), |w| {
+      w.write_u8(length);
+      w.push_bytes(bytes);
+    }))
+  }
+
+  /// Writes a whole serialized message (e.g. another writer's data) as a u32 length followed by its
+  /// bytes, so several messages can be appended to one stream and split apart with
+  /// `BinaryReader::messages`.
+  pub fn write_message(&mut self, message: &[u8]) -> &mut Self {
+    self.traced(|| Token::Vec(message.len()), |w| {
+      w.write_count(message.len());
+      w.push_bytes(message);
+    })
+  }
+
+  /// Writes a nested section of any shape: `f` writes the body into a temporary writer, then the
+  /// body's byte count goes out as a prefix of the given width, followed by the body. Read it back
+  /// with `BinaryReader::read_len_prefixed`. The temporary writer shares this writer's byte order,
+  /// NaN canonicalization and tracing, and may only use what is left of its size limit. An error
+  /// raised inside `f` fails this writer too and is returned. Errors without writing anything if the
+  /// body is too long for the prefix.
+  pub fn write_len_prefixed<F: FnOnce(&mut BinaryWriter)>(&mut self, prefix: PrefixWidth, f: F) -> Result<&mut Self, BinaryError> {
+    let mut body = BinaryWriter::new_with_endianness(self.endianness);
+    body.canonical_nan = self.canonical_nan;
+    body.max_size = self.max_size.map(|limit| limit.saturating_sub(self.written).saturating_sub(prefix.min_size()));
+    if self.trace.is_some() {
+      body.trace = Some(Vec::new());
+    }
+    f(&mut body);
+    let tokens = body.trace.take();
+    let body = match body.finish() {
+      Ok(body) => body,
+      Err(e) => {
+        // The body's own limit is derived from this writer's; report the one the caller set.
+        let e = match (e, self.max_size) {
+          (BinaryError::SizeLimitExceeded { .. }, Some(limit)) => BinaryError::SizeLimitExceeded { limit },
+          (e, _) => e,
+        };
+        if self.error.is_none() {
+          self.error = Some(e.clone());
+        }
+        return Err(e);
+      }
+    };
+    if body.len() as u64 > prefix.max_length() {
+      return Err(BinaryError::LengthOverflow { length: body.len(), max: prefix.max_length() });
+    }
+    self.traced(|| Token::Vec(body.len()), |w| {
+      // The length was checked above, so this cannot fail.
+      let _ = w.write_length(body.len(), prefix);
+      w.push_bytes(&body);
+    });
+    if let (Some(trace), Some(tokens)) = (self.trace.as_mut(), tokens) {
+      trace.extend(tokens);
+    }
+    match &self.error {
+      Some(e) => Err(e.clone()),
+      None => Ok(self),
+    }
+  }
+
+  /// Writes a vector of u16 to the buffer. First writes the length as u32, then the values in the writer's byte order.
+  pub fn write_vec_u16(&mut self, value: &[u16]) -> &mut Self {
+    self.traced(|| Token::Vec(value.len()), |w| {
+      w.write_vec_fixed(value, u16::to_le_bytes);
+    })
+  }
+
+  /// Writes a vector of u32 to the buffer. First writes the length as u32, then the values in the writer's byte order.
+  pub fn write_vec_u32(&mut self, value: &[u32]) -> &mut Self {
+    self.traced(|| Token::Vec(value.len()), |w| {
+      w.write_vec_fixed(value, u32::to_le_bytes);
+    })
+  }
+
+  /// Writes a vector of u64 to the buffer. First writes the length as u32, then the values in the writer's byte order.
+  pub fn write_vec_u64(&mut self, value: &[u64]) -> &mut Self {
+    self.traced(|| Token::Vec(value.len()), |w| {
+      w.write_vec_fixed(value, u64::to_le_bytes);
+    })
+  }
+
+  /// Writes a vector of 24-bit unsigned values: the length as u32, then 3 bytes per value as
+  /// `write_u24` writes them. Errors without writing anything if any value is above `0xFF_FFFF`.
+  pub fn write_vec_u24(&mut self, value: &[u32]) -> Result<&mut Self, BinaryError> {
+    value.iter().try_for_each(|&v| check_u24(v))?;
+    Ok(self.traced(|| Token::Vec(value.len()), |w| {
+      w.write_count(value.len());
+      for &v in value {
+        w.push_u24(v);
+      }
+    }))
+  }
+
+  /// Writes a vector of 24-bit signed values: the length as u32, then 3 bytes per value as
+  /// `write_i24` writes them. Errors without writing anything if any value is out of range.
+  pub fn write_vec_i24(&mut self, value: &[i32]) -> Result<&mut Self, BinaryError> {
+    value.iter().try_for_each(|&v| check_i24(v))?;
+    Ok(self.traced(|| Token::Vec(value.len()), |w| {
+      w.write_count(value.len());
+      for &v in value {
+        w.push_u24(v as u32);
+      }
+    }))
+  }
+
+  /// Writes a vector of i8 to the buffer. First writes the length as u32, then the bytes.
+  pub fn write_vec_i8(&mut self, value: &[i8]) -> &mut Self {
+    self.traced(|| Token::Vec(value.len()), |w| {
+      w.write_count(value.len());
+      // SAFETY: i8 and u8 have the same size and alignment and every bit pattern is valid for both,
+      // so the slice's memory is `value.len()` initialized bytes holding the two's-complement values.
+      let bytes = unsafe { std::slice::from_raw_parts(value.as_ptr() as *const u8, value.len()) };
+      w.push_bytes(bytes);
+    })
+  }
+
+  /// Writes a vector of i16 to the buffer. First writes the length as u32, then the values in the writer's byte order.
+  pub fn write_vec_i16(&mut self, value: &[i16]) -> &mut Self {
+    self.traced(|| Token::Vec(value.len()), |w| {
+      w.write_vec_fixed(value, i16::to_le_bytes);
+    })
+  }
+
+  /// Writes a vector of i32 to the buffer. First writes the length as u32, then the values in the writer's byte order.
+  pub fn write_vec_i32(&mut self, value: &[i32]) -> &mut Self {
+    self.traced(|| Token::Vec(value.len()), |w| {
+      w.write_vec_fixed(value, i32::to_le_bytes);
+    })
+  }
+
+  /// Writes a vector of i64 to the buffer. First writes the length as u32, then the values in the writer's byte order.
+  pub fn write_vec_i64(&mut self, value: &[i64]) -> &mut Self {
+    self.traced(|| Token::Vec(value.len()), |w| {
+      w.write_vec_fixed(value, i64::to_le_bytes);
+    })
+  }
+
+  /// Writes a vector of f32 to the buffer. First writes the length as u32, then the values in the writer's byte order.
+  pub fn write_vec_f32(&mut self, value: &[f32]) -> &mut Self {
+    self.traced(|| Token::Vec(value.len()), |w| {
+      if w.canonical_nan {
+        // NaNs have to be rewritten one at a time.
+        w.write_count(value.len());
+        for &v in value {
+          w.write_f32(v);
+        }
+      } else {
+        w.write_vec_fixed(value, f32::to_le_bytes);
+      }
+    })
+  }
+
+  /// Writes a vector of f64 to the buffer. First writes the length as u32, then the values in the writer's byte order.
+  pub fn write_vec_f64(&mut self, value: &[f64]) -> &mut Self {
+    self.traced(|| Token::Vec(value.len()), |w| {
+      if w.canonical_nan {
+        // NaNs have to be rewritten one at a time.
+        w.write_count(value.len());
+        for &v in value {
+          w.write_f64(v);
+        }
+      } else {
+        w.write_vec_fixed(value, f64::to_le_bytes);
+      }
+    })
+  }
+
+  /// Writes `length` as a prefix of the given width, erroring if it does not fit.
+  fn write_length(&mut self, length: usize, prefix: PrefixWidth) -> Result<(), BinaryError> {
+    if length as u64 > prefix.max_length() {
+      return Err(BinaryError::LengthOverflow { length, max: prefix.max_length() });
+    }
+    match prefix {
+      PrefixWidth::U8 => self.write_u8(length as u8),
+      PrefixWidth::U16 => self.write_u16(length as u16),
+      PrefixWidth::U32 => self.write_u32(length as u32),
+      PrefixWidth::U64 => self.write_u64(length as u64),
+      PrefixWidth::Varint => self.write_varint(length as u64),
+    };
+    Ok(())
+  }
+
+  /// Appends the low 3 bytes of `value` in the writer's byte order.
+  fn push_u24(&mut self, value: u32) {
+    let [b0, b1, b2, _] = value.to_le_bytes();
+    self.push_ordered([b0, b1, b2]);
+  }
+
+  /// Appends little-endian `bytes`, reversed first if the writer is big-endian.
+  fn push_ordered<const N: usize>(&mut self, mut bytes: [u8; N]) {
+    if self.endianness == Endianness::Big {
+      bytes.reverse();
+    }
+    self.push_bytes(&bytes);
+  }
+
+  /// Appends raw bytes to the sink, updating the hasher if one is set. Once the sink has failed or
+  /// the size limit was hit, further bytes are dropped so `finish` can report the first error.
+  fn push_bytes(&mut self, bytes: &[u8]) {
+    if self.error.is_some() {
+      return;
+    }
+    if let Some(limit) = self.max_size {
+      if bytes.len() > limit - self.written {
+        self.error = Some(BinaryError::SizeLimitExceeded { limit });
+        return;
+      }
+    }
+    match self.sink.push_bytes(bytes) {
+      Ok(()) => {
+        self.written += bytes.len();
+        if let Some(hasher) = self.hasher.as_mut() {
+          hasher.write(bytes);
+        }
+      }
+      Err(e) => self.error = Some(e),
+    }
+  }
+
+  /// Writes a length or count as a u32. One too large for a u32 fails the writer with
+  /// `LengthOverflow` instead of writing a truncated value, so nothing after it reaches the buffer
+  /// and `finish` (or `try_write`) reports the error.
+  fn write_count(&mut self, count: usize) {
+    match u32::try_from(count) {
+      Ok(count) => {
+        self.write_u32(count);
+      }
+      Err(_) => {
+        if self.error.is_none() {
+          self.error = Some(BinaryError::LengthOverflow { length: count, max: u32::MAX as u64 });
+        }
+      }
+    }
+  }
+
+  /// Writes a u32 length followed by each `N`-byte value in the writer's byte order.
+  fn write_vec_fixed<T: Copy, const N: usize>(&mut self, value: &[T], to_le: fn(T) -> [u8; N]) {
+    self.write_count(value.len());
+    self.write_fixed_slice(value, to_le);
+  }
+
+  /// Writes each `N`-byte value in the writer's byte order, with no length prefix. When that order
+  /// matches the host's, the slice's memory already has the wire layout and is appended in one copy.
+  fn write_fixed_slice<T: Copy, const N: usize>(&mut self, value: &[T], to_le: fn(T) -> [u8; N]) {
+    debug_assert_eq!(std::mem::size_of::<T>(), N);
+    if self.endianness == Endianness::NATIVE {
+      // SAFETY: `T` is only ever a primitive integer or float of exactly `N` bytes with no padding,
+      // so the slice is `size_of_val(value)` initialized bytes.
+      let bytes = unsafe { std::slice::from_raw_parts(value.as_ptr() as *const u8, std::mem::size_of_val(value)) };
+      self.push_bytes(bytes);
+    } else {
+      for &v in value {
+        self.push_ordered(to_le(v));
+      }
+    }
+  }
+
+  /// Writes two parallel slices under a single u32 length: first all the keys, then all the values.
+  /// Errors without writing anything if the slices differ in length.
+  pub fn write_pairs_u32_f64(&mut self, keys: &[u32], vals: &[f64]) -> Result<&mut Self, BinaryError> {
+    if keys.len() != vals.len() {
+      return Err(BinaryError::LengthMismatch { left: keys.len(), right: vals.len() });
+    }
+    Ok(self.traced(|| Token::Vec(keys.len()), |w| {
+      w.write_count(keys.len());
+      w.write_fixed_slice(keys, u32::to_le_bytes);
+      if w.canonical_nan {
+        for &v in vals {
+          w.write_f64(v);
+        }
+      } else {
+        w.write_fixed_slice(vals, f64::to_le_bytes);
+      }
+    }))
+  }
+
+  /// Writes a vector of chars to the buffer. First writes the length as u32, then each code point as u32.
+  pub fn write_vec_char(&mut self, value: &[char]) -> &mut Self {
+    self.traced(|| Token::Vec(value.len()), |w| {
+      w.write_count(value.len());
+      for &v in value {
+        w.write_char(v);
+      }
+    })
+  }
+
+  /// Writes a vector of bools run-length encoded: the total length as a varint, then the lengths of
+  /// alternating runs of `false` and `true` values as varints, starting with `false` (so a vector
+  /// that starts with `true` begins with a zero-length run). Mostly uniform vectors shrink to a few
+  /// bytes.
+  pub fn write_vec_bool_rle(&mut self, bits: &[bool]) -> &mut Self {
+    self.traced(|| Token::Vec(bits.len()), |w| {
+      w.write_varint(bits.len() as u64);
+      let mut current = false;
+      let mut run = 0u64;
+      for &bit in bits {
+        if bit != current {
+          w.write_varint(run);
+          current = bit;
+          run = 0;
+        }
+        run += 1;
+      }
+      if run > 0 {
+        w.write_varint(run);
+      }
+    })
+  }
+
+  /// Writes a vector of strings to the buffer. First writes the length as u32, then each string serialized.
+  pub fn write_vec_string(&mut self, value: &[String]) -> &mut Self {
+    self.traced(|| Token::Vec(value.len()), |w| {
+      w.write_count(value.len());
+      for s in value {
+        w.write_string(s);
+      }
+    })
+  }
+
+  /// Writes a vector of byte blobs to the buffer. First writes the count as u32, then each blob as
+  /// `write_vec_u8` would.
+  pub fn write_vec_bytes(&mut self, value: &[Vec<u8>]) -> &mut Self {
+    self.traced(|| Token::Vec(value.len()), |w| {
+      w.write_count(value.len());
+      for bytes in value {
+        w.write_vec_u8(bytes);
+      }
+    })
+  }
+
+  /// Writes a vector of strings in packed form for O(1) random access: the count as u32, then
+  /// count + 1 u32 offsets into the string data, then all UTF-8 bytes concatenated.
+  pub fn write_vec_string_packed(&mut self, value: &[String]) -> &mut Self {
+    self.traced(|| Token::Vec(value.len()), |w| {
+      w.write_count(value.len());
+      let mut offset = 0usize;
+      w.write_count(offset);
+      for s in value {
+        offset = offset.saturating_add(s.len());
+        w.write_count(offset);
+      }
+      for s in value {
+        w.push_bytes(s.as_bytes());
+      }
+    })
+  }
+
+  /// Interns `s` in `interner` and writes its table index as a u32 in place of the string, so a
+  /// string repeated throughout a message is stored once. Finish with `finish_interned` to emit the
+  /// table.
+  pub fn write_interned(&mut self, interner: &mut StringInterner, s: &str) -> &mut Self {
+    let index = interner.intern(s);
+    self.write_u32(index)
+  }
+
+  /// Writes `count` copies of `byte` to the buffer, e.g. to reserve space or pad a record.
+  pub fn write_padding(&mut self, count: usize, byte: u8) -> &mut Self {
+    self.traced(|| Token::Padding(count), |w| {
+      let chunk = [byte; 64];
+      let mut left = count;
+      while left > 0 {
+        let n = left.min(chunk.len());
+        w.push_bytes(&chunk[..n]);
+        left -= n;
+      }
+    })
+  }
+
+  /// Writes any value implementing `Writable` to the buffer.
+  pub fn write<T: Writable + ?Sized>(&mut self, value: &T) -> &mut Self {
+    value.write_to(self);
+    self
+  }
+
+  /// Writes a vector of any `Writable` type. First writes the length as u32, then each element.
+  pub fn write_vec<T: Writable>(&mut self, value: &[T]) -> &mut Self {
+    self.traced(|| Token::Vec(value.len()), |w| {
+      w.write_count(value.len());
+      for v in value {
+        v.write_to(w);
+      }
+    })
+  }
+
+  /// Writes a vector like `write_vec`, with a LEB128 varint element count instead of a u32.
+  pub fn write_vec_varint<T: Writable>(&mut self, value: &[T]) -> &mut Self {
+    // A varint holds any count, so this cannot fail.
+    let _ = self.write_vec_with_prefix(value, PrefixWidth::Varint);
+    self
+  }
+
+  /// Writes a vector like `write_vec`, with an element count prefix of the given width. Errors
+  /// without writing anything if there are too many elements for the prefix.
+  pub fn write_vec_with_prefix<T: Writable>(&mut self, value: &[T], prefix: PrefixWidth) -> Result<&mut Self, BinaryError> {
+    if value.len() as u64 > prefix.max_length() {
+      return Err(BinaryError::LengthOverflow { length: value.len(), max: prefix.max_length() });
+    }
+    Ok(self.traced(|| Token::Vec(value.len()), |w| {
+      // The count was checked above, so this cannot fail.
+      let _ = w.write_length(value.len(), prefix);
+      for v in value {
+        v.write_to(w);
+      }
+    }))
+  }
+
+  /// Writes a set in ascending order: first the count as u32, then each element. Equal sets always
+  /// produce identical bytes.
+  pub fn write_set<T: Writable + Ord>(&mut self, set: &BTreeSet<T>) -> &mut Self {
+    self.write_elements(set.len(), set.iter());
+    self
+  }
+
+  /// Writes a hash set in the same format as `write_set`, sorting the elements first so equal sets
+  /// produce identical bytes. Writing a `HashSet` with `write` skips the sort and uses its
+  /// iteration order, which varies between runs.
+  pub fn write_hash_set_sorted<T: Writable + Ord, H>(&mut self, set: &HashSet<T, H>) -> &mut Self {
+    let mut sorted: Vec<&T> = set.iter().collect();
+    sorted.sort();
+    self.write_elements(sorted.len(), sorted.into_iter());
+    self
+  }
+
+  /// Writes a u32 count followed by each element, the same layout as `write_vec`.
+  fn write_elements<'e, T: Writable + 'e>(&mut self, len: usize, elements: impl Iterator<Item = &'e T>) {
+    self.traced(|| Token::Vec(len), |w| {
+      w.write_count(len);
+      for v in elements {
+        v.write_to(w);
+      }
+    });
+  }
+
+  /// Writes an `Option` as a u8 presence byte (0 for `None`, 1 for `Some`) followed by the value.
+  pub fn write_option<T: Writable>(&mut self, value: &Option<T>) -> &mut Self {
+    match value {
+      None => self.write_u8(0),
+      Some(v) => self.write_u8(1).write(v),
+    }
+  }
+
+  /// Writes a vector of options densely, for sparse data: the count as u32, then a bitmap with one
+  /// bit per element (least significant bit first, set when present), then only the present values.
+  /// A `Vec<Option<T>>` written with `write` instead spends a whole byte on each element's presence.
+  pub fn write_vec_option_packed<T: Writable>(&mut self, value: &[Option<T>]) -> &mut Self {
+    self.traced(|| Token::Vec(value.len()), |w| {
+      w.write_count(value.len());
+      let mut bitmap = vec![0u8; value.len().div_ceil(8)];
+      for (i, v) in value.iter().enumerate() {
+        if v.is_some() {
+          bitmap[i / 8] |= 1 << (i % 8);
+        }
+      }
+      w.push_bytes(&bitmap);
+      for v in value.iter().flatten() {
+        v.write_to(w);
+      }
+    })
+  }
+
+  /// Writes a `Result` as a u8 tag (0 for `Ok`, 1 for `Err`) followed by the payload.
+  pub fn write_result<T: Writable, E: Writable>(&mut self, value: &Result<T, E>) -> &mut Self {
+    match value {
+      Ok(v) => self.write_u8(0).write(v),
+      Err(e) => self.write_u8(1).write(e),
+    }
+  }
+
+  /// Writes a fixed-point decimal as its scaled integer (i64) followed by the scale (u8), the
+  /// number of decimal places: 123.45 is `write_fixed_point(12345, 2)`. Errors without writing
+  /// anything if `scale` is above 38, the most any decimal type supports.
+  pub fn write_fixed_point(&mut self, value: i64, scale: u8) -> Result<&mut Self, BinaryError> {
+    check_scale(scale)?;
+    Ok(self.write_i64(value).write_u8(scale))
+  }
+
+  /// Writes a set of packed flags as a u32, e.g. the `bits` of a `FlagSet`.
+  pub fn write_flags(&mut self, bits: u32) -> &mut Self {
+    self.write_u32(bits)
+  }
+
+  /// Writes a `Range` as its `start` followed by its `end`.
+  pub fn write_range<T: Writable>(&mut self, value: &Range<T>) -> &mut Self {
+    self.write(&value.start).write(&value.end)
+  }
+
+  /// Writes a `RangeInclusive` as its `start` followed by its (inclusive) `end`.
+  pub fn write_range_inclusive<T: Writable>(&mut self, value: &RangeInclusive<T>) -> &mut Self {
+    self.write(value.start()).write(value.end())
+  }
+
+  /// Writes the `N` elements of a fixed-size array in order, with no length prefix since the reader
+  /// knows `N` statically. Works for any `Writable` element type, including derived structs.
+  pub fn write_array<T: Writable, const N: usize>(&mut self, value: &[T; N]) -> &mut Self {
+    for v in value {
+      v.write_to(self);
+    }
+    self
+  }
+
+  /// Writes an IPv4 address as its 4 octets in network order, with no family tag, for layouts where
+  /// the family is known from context.
+  pub fn write_ipv4(&mut self, addr: Ipv4Addr) -> &mut Self {
+    self.write_array(&addr.octets())
+  }
+
+  /// Writes an IPv6 address as its 16 octets in network order, with no family tag.
+  pub fn write_ipv6(&mut self, addr: Ipv6Addr) -> &mut Self {
+    self.write_array(&addr.octets())
+  }
+
+  /// Writes map entries: first the count as u32, then each key followed by its value.
+  fn write_entries<'e, K: Writable + 'e, V: Writable + 'e>(&mut self, len: usize, entries: impl Iterator<Item = (&'e K, &'e V)>) {
+    self.traced(|| Token::Map(len), |w| {
+      w.write_count(len);
+      for (k, v) in entries {
+        k.write_to(w);
+        v.write_to(w);
+      }
+    });
+  }
+
+  /// Returns `value`, or the canonical quiet NaN if it is a NaN and canonicalization is enabled.
+  fn canonical_f32(&self, value: f32) -> f32 {
+    if self.canonical_nan && value.is_nan() {
+      f32::from_bits(0x7fc0_0000)
+    } else {
+      value
+    }
+  }
+
+  /// Returns `value`, or the canonical quiet NaN if it is a NaN and canonicalization is enabled.
+  fn canonical_f64(&self, value: f64) -> f64 {
+    if self.canonical_nan && value.is_nan() {
+      f64::from_bits(0x7ff8_0000_0000_0000)
+    } else {
+      value
+    }
+  }
+
+  /// Records `token` in the trace, if there is one, then runs `write`. The trace is set aside while
+  /// `write` runs, so the values it writes internally (such as a string's length prefix or a
+  /// vector's elements) are covered by `token` rather than recorded separately.
+  fn traced(&mut self, token: impl FnOnce() -> Token, write: impl FnOnce(&mut Self)) -> &mut Self {
+    #[cfg(feature = "debug-schema")]
+    let nested = std::mem::replace(&mut self.schema.nested, true);
+    #[cfg(feature = "debug-schema")]
+    let token = {
+      let token = token();
+      if !nested {
+        self.schema.entries.push((token.type_name(), self.written));
+      }
+      move || token
+    };
+    match self.trace.take() {
+      Some(mut trace) => {
+        trace.push(token());
+        write(self);
+        self.trace = Some(trace);
+      }
+      None => write(self),
+    }
+    #[cfg(feature = "debug-schema")]
+    {
+      self.schema.nested = nested;
+    }
+    self
+  }
+}
+
+/// BinaryReader is used to deserialize various data types from a byte buffer.
+pub struct BinaryReader<'a> {
+  data: &'a [u8],
+  cursor: usize,
+  endianness: Endianness,
+  #[cfg(feature = "debug-schema")]
+  schema: SchemaTrace,
+  accounting: Option<Vec<(String, usize)>>,
+}
+
+impl fmt::Debug for BinaryReader<'_> {
+  /// Prints the cursor position followed by a hex dump of the buffer, with a `^^` marker
+  /// under the next byte to be read.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "BinaryReader (cursor {} of {} bytes)", self.cursor, self.data.len())?;
+    let cursor_line = self.cursor / 16;
+    for (i, line) in hex_dump(self.data).lines().enumerate() {
+      writeln!(f, "{}", line)?;
+      if i == cursor_line {
+        let column = self.cursor % 16;
+        writeln!(f, "{:width$}^^", "", width = 10 + column * 2 + column / 2)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+impl<'a> BinaryReader<'a> {
+  /// Creates a new BinaryReader with the given byte slice.
+  pub fn new(data: &'a [u8]) -> Self {
+    BinaryReader::new_with_endianness(data, Endianness::Little)
+  }
+
+  /// Creates a new BinaryReader with the given byte slice that reads multi-byte values in the given
+  /// byte order, for formats whose order is only known at runtime (e.g. from a header flag).
+  pub fn new_with_endianness(data: &'a [u8], endianness: Endianness) -> Self {
+    BinaryReader {
+      data,
+      cursor: 0,
+      endianness,
+      #[cfg(feature = "debug-schema")]
+      schema: SchemaTrace::default(),
+      accounting: None,
+    }
+  }
+
+  /// Creates a reader over the body of `data`, a buffer ending in a checksum footer as written by
+  /// `BinaryWriter::finish_with_checksum`. The footer is checked against the body up front, and the
+  /// reader is bounded to the body, so reads cannot run into the checksum bytes. Errors with
+  /// `ChecksumMismatch` if the body is corrupted, or `UnexpectedEof` if `data` is too short for a footer.
+  pub fn new_verified(data: &'a [u8], kind: ChecksumKind) -> Result<Self, BinaryError> {
+    let Some(body_len) = data.len().checked_sub(4) else {
+      return Err(BinaryError::UnexpectedEof { needed: 4, available: data.len(), offset: 0 });
+    };
+    let (body, footer) = data.split_at(body_len);
+    let expected = u32::from_le_bytes(footer.try_into().unwrap());
+    let found = kind.checksum(body);
+    if expected != found {
+      return Err(BinaryError::ChecksumMismatch { expected, found });
+    }
+    Ok(BinaryReader::new(body))
+  }
+
+  /// Turns on byte accounting: every read made through `read_labeled` or `labeled` is recorded with
+  /// the number of bytes it consumed, for finding the fields that dominate a format's size. Without
+  /// it, labeled reads cost nothing extra.
+  pub fn with_accounting(mut self) -> Self {
+    self.accounting = Some(Vec::new());
+    self
+  }
+
+  /// Returns the `(label, bytes)` pairs recorded so far, in read order. Empty unless the reader was
+  /// created `with_accounting`.
+  pub fn account(&self) -> &[(String, usize)] {
+    self.accounting.as_deref().unwrap_or_default()
+  }
+
+  /// Reads a `T` like `read`, recording the bytes it took under `label` when accounting is on.
+  pub fn read_labeled<T: Readable>(&mut self, label: &str) -> Result<T, BinaryError> {
+    self.labeled(label, T::read_from)
+  }
+
+  /// Runs `read` (any sequence of reads), recording how far it moved the cursor forward under
+  /// `label` when accounting is on. A `read` that peeks and rewinds with `restore` records only
+  /// what it left consumed, and one that ends before where it started records 0. Failed reads are
+  /// not recorded.
+  pub fn labeled<T>(&mut self, label: &str, read: impl FnOnce(&mut Self) -> Result<T, BinaryError>) -> Result<T, BinaryError> {
+    if self.accounting.is_none() {
+      return read(self);
+    }
+    let start = self.cursor;
+    let value = read(self)?;
+    let used = self.cursor.saturating_sub(start);
+    if let Some(accounting) = &mut self.accounting {
+      accounting.push((label.to_string(), used));
+    }
+    Ok(value)
+  }
+
+  /// Returns the byte order used for multi-byte values.
+  pub fn endianness(&self) -> Endianness {
+    self.endianness
+  }
+
+  /// Sets the byte order used for multi-byte values, including length prefixes.
+  pub fn set_endianness(&mut self, endianness: Endianness) {
+    self.endianness = endianness;
+  }
+
+  /// Returns the number of bytes left to read.
+  pub fn remaining(&self) -> usize {
+    self.data.len() - self.cursor
+  }
+
+  /// Consumes the reader, erroring with `TrailingBytes` unless every byte has been read. Call it
+  /// after decoding a message to catch a forgotten field or a wrong length.
+  pub fn finish(self) -> Result<(), BinaryError> {
+    match self.remaining() {
+      0 => Ok(()),
+      remaining => Err(BinaryError::TrailingBytes { remaining }),
+    }
+  }
+
+  /// Returns all bytes from the cursor to the end of the buffer without advancing, e.g. to hand the
+  /// unparsed tail of a message to another subsystem without copying it.
+  pub fn remaining_slice(&self) -> &'a [u8] {
+    &self.data[self.cursor..]
+  }
+
+  /// Returns all bytes from the cursor to the end of the buffer and advances to the end. Useful when
+  /// the last field of a message is the rest of the buffer, with no length prefix.
+  pub fn read_remaining(&mut self) -> &'a [u8] {
+    let rest = self.remaining_slice();
+    self.cursor = self.data.len();
+    rest
+  }
+
+  /// Returns an owned copy of all bytes from the cursor to the end and advances to the end.
+  pub fn read_remaining_vec(&mut self) -> Vec<u8> {
+    self.read_remaining().to_vec()
+  }
+
+  /// Reads a u8 value from the buffer.
+  pub fn read_u8(&mut self) -> Result<u8, BinaryError> {
+    self.schema("u8", |r| {
+      r.ensure_available(1)?;
+      let value = r.data[r.cursor];
+      r.cursor += 1;
+      Ok(value)
+    })
+  }
+
+  /// Reads a u16 value from the buffer in the reader's byte order (little-endian by default).
+  pub fn read_u16(&mut self) -> Result<u16, BinaryError> {
+    self.schema("u16", |r| r.read_ordered::<2>().map(u16::from_le_bytes))
+  }
+
+  /// Reads a u32 value from the buffer in the reader's byte order (little-endian by default).
+  pub fn read_u32(&mut self) -> Result<u32, BinaryError> {
+    self.schema("u32", |r| r.read_ordered::<4>().map(u32::from_le_bytes))
+  }
+
+  /// Reads a u64 value from the buffer in the reader's byte order (little-endian by default).
+  pub fn read_u64(&mut self) -> Result<u64, BinaryError> {
+    self.schema("u64", |r| r.read_ordered::<8>().map(u64::from_le_bytes))
+  }
+
+  /// Reads a 3-byte unsigned value written by `write_u24`, in the reader's byte order.
+  pub fn read_u24(&mut self) -> Result<u32, BinaryError> {
+    self.schema("u24", |r| r.read_ordered::<3>().map(|[b0, b1, b2]| u32::from_le_bytes([b0, b1, b2, 0])))
+  }
+
+  /// Reads a 3-byte signed value written by `write_i24`, sign-extending it to an i32.
+  pub fn read_i24(&mut self) -> Result<i32, BinaryError> {
+    self.schema("i24", |r| r.read_ordered::<3>().map(|[b0, b1, b2]| i32::from_le_bytes([0, b0, b1, b2]) >> 8))
+  }
+
+  /// Reads an i8 value from the buffer.
+  pub fn read_i8(&mut self) -> Result<i8, BinaryError> {
+    self.schema("i8", |r| {
+      r.ensure_available(1)?;
+      let value = i8::from_le_bytes([r.data[r.cursor]]);
+      r.cursor += 1;
+      Ok(value)
+    })
+  }
+
+  /// Reads an i16 value from the buffer in the reader's byte order (little-endian by default).
+  pub fn read_i16(&mut self) -> Result<i16, BinaryError> {
+    self.schema("i16", |r| r.read_ordered::<2>().map(i16::from_le_bytes))
+  }
+
+  /// Reads an i32 value from the buffer in the reader's byte order (little-endian by default).
+  pub fn read_i32(&mut self) -> Result<i32, BinaryError> {
+    self.schema("i32", |r| r.read_ordered::<4>().map(i32::from_le_bytes))
+  }
+
+  /// Reads an i64 value from the buffer in the reader's byte order (little-endian by default).
+  pub fn read_i64(&mut self) -> Result<i64, BinaryError> {
+    self.schema("i64", |r| r.read_ordered::<8>().map(i64::from_le_bytes))
+  }
+
+  /// Reads a LEB128 varint written by `write_varint`. At most 10 bytes are examined; a longer
+  /// encoding, or one whose value overflows u64, is an `InvalidValue` error.
+  pub fn read_varint(&mut self) -> Result<u64, BinaryError> {
+    self.schema("varint", |r| {
+      let mut value = 0u64;
+      for index in 0..10 {
+        let byte = r.read_u8()?;
+        let bits = (byte & 0x7F) as u64;
+        // The tenth byte holds only the top bit of a u64.
+        if index == 9 && bits > 1 {
+          break;
+        }
+        value |= bits << (7 * index);
+        if byte & 0x80 == 0 {
+          return Ok(value);
+        }
+      }
+      Err(BinaryError::InvalidValue("varint overflows u64".to_string()))
+    })
+  }
+
+  /// Reads a usize value from the buffer. Expects a u64 and errors if it exceeds `usize::MAX`
+  /// on the current target (possible on 32-bit platforms).
+  pub fn read_usize(&mut self) -> Result<usize, BinaryError> {
+    self.schema("usize", |r| {
+      let value = r.read_u64()?;
+      fit_usize(value, usize::MAX as u64)
+    })
+  }
+
+  /// Reads an isize value from the buffer. Expects an i64 and errors if it is outside the range
+  /// of `isize` on the current target (possible on 32-bit platforms).
+  pub fn read_isize(&mut self) -> Result<isize, BinaryError> {
+    self.schema("isize", |r| {
+      let value = r.read_i64()?;
+      isize::try_from(value).map_err(|_| BinaryError::IsizeOverflow(value))
+    })
+  }
+
+  /// Reads a f32 value from the buffer in the reader's byte order (little-endian by default).
+  pub fn read_f32(&mut self) -> Result<f32, BinaryError> {
+    self.schema("f32", |r| r.read_ordered::<4>().map(f32::from_le_bytes))
+  }
+
+  /// Reads a f64 value from the buffer in the reader's byte order (little-endian by default).
+  pub fn read_f64(&mut self) -> Result<f64, BinaryError> {
+    self.schema("f64", |r| r.read_ordered::<8>().map(f64::from_le_bytes))
+  }
+
+  /// Reads a f32 like `read_f32`, but errors with `InvalidValue` if it is NaN or infinite.
+  pub fn read_f32_finite(&mut self) -> Result<f32, BinaryError> {
+    self.schema("f32", |r| {
+      let value = r.read_f32()?;
+      if !value.is_finite() {
+        return Err(BinaryError::InvalidValue(format!("expected a finite f32, found {}", value)));
+      }
+      Ok(value)
+    })
+  }
+
+  /// Reads a f64 like `read_f64`, but errors with `InvalidValue` if it is NaN or infinite.
+  pub fn read_f64_finite(&mut self) -> Result<f64, BinaryError> {
+    self.schema("f64", |r| {
+      let value = r.read_f64()?;
+      if !value.is_finite() {
+        return Err(BinaryError::InvalidValue(format!("expected a finite f64, found {}", value)));
+      }
+      Ok(value)
+    })
+  }
+
+  /// Reads the raw bit pattern of a f32 from the buffer in the reader's byte order, without passing
+  /// through a float value, so NaN payloads can be inspected exactly.
+  pub fn read_f32_bits(&mut self) -> Result<u32, BinaryError> {
+    self.schema("f32", |r| r.read_u32())
+  }
+
+  /// Reads the raw bit pattern of a f64 from the buffer in the reader's byte order, without passing
+  /// through a float value, so NaN payloads can be inspected exactly.
+  pub fn read_f64_bits(&mut self) -> Result<u64, BinaryError> {
+    self.schema("f64", |r| r.read_u64())
+  }
+
+  /// Reads a u16 value from the buffer in little-endian order, regardless of the default byte order.
+  pub fn read_u16_le(&mut self) -> Result<u16, BinaryError> {
+    self.schema("u16", |r| r.read_byte_array::<2>().map(u16::from_le_bytes))
+  }
+
+  /// Reads a u32 value from the buffer in little-endian order, regardless of the default byte order.
+  pub fn read_u32_le(&mut self) -> Result<u32, BinaryError> {
+    self.schema("u32", |r| r.read_byte_array::<4>().map(u32::from_le_bytes))
+  }
+
+  /// Reads a u64 value from the buffer in little-endian order, regardless of the default byte order.
+  pub fn read_u64_le(&mut self) -> Result<u64, BinaryError> {
+    self.schema("u64", |r| r.read_byte_array::<8>().map(u64::from_le_bytes))
+  }
+
+  /// Reads an i16 value from the buffer in little-endian order, regardless of the default byte order.
+  pub fn read_i16_le(&mut self) -> Result<i16, BinaryError> {
+    self.schema("i16", |r| r.read_byte_array::<2>().map(i16::from_le_bytes))
+  }
+
+  /// Reads an i32 value from the buffer in little-endian order, regardless of the default byte order.
+  pub fn read_i32_le(&mut self) -> Result<i32, BinaryError> {
+    self.schema("i32", |r| r.read_byte_array::<4>().map(i32::from_le_bytes))
+  }
+
+  /// Reads an i64 value from the buffer in little-endian order, regardless of the default byte order.
+  pub fn read_i64_le(&mut self) -> Result<i64, BinaryError> {
+    self.schema("i64", |r| r.read_byte_array::<8>().map(i64::from_le_bytes))
+  }
+
+  /// Reads a f32 value from the buffer in little-endian order, regardless of the default byte order.
+  pub fn read_f32_le(&mut self) -> Result<f32, BinaryError> {
+    self.schema("f32", |r| r.read_byte_array::<4>().map(f32::from_le_bytes))
+  }
+
+  /// Reads a f64 value from the buffer in little-endian order, regardless of the default byte order.
+  pub fn read_f64_le(&mut self) -> Result<f64, BinaryError> {
+    self.schema("f64", |r| r.read_byte_array::<8>().map(f64::from_le_bytes))
+  }
+
+  /// Reads a u16 value from the buffer in big-endian order, regardless of the default byte order.
+  pub fn read_u16_be(&mut self) -> Result<u16, BinaryError> {
+    self.schema("u16", |r| r.read_byte_array::<2>().map(u16::from_be_bytes))
+  }
+
+  /// Reads a u32 value from the buffer in big-endian order, regardless of the default byte order.
+  pub fn read_u32_be(&mut self) -> Result<u32, BinaryError> {
+    self.schema("u32", |r| r.read_byte_array::<4>().map(u32::from_be_bytes))
+  }
+
+  /// Reads a u64 value from the buffer in big-endian order, regardless of the default byte order.
+  pub fn read_u64_be(&mut self) -> Result<u64, BinaryError> {
+    self.schema("u64", |r| r.read_byte_array::<8>().map(u64::from_be_bytes))
+  }
+
+  /// Reads an i16 value from the buffer in big-endian order, regardless of the default byte order.
+  pub fn read_i16_be(&mut self) -> Result<i16, BinaryError> {
+    self.schema("i16", |r| r.read_byte_array::<2>().map(i16::from_be_bytes))
+  }
+
+  /// Reads an i32 value from the buffer in big-endian order, regardless of the default byte order.
+  pub fn read_i32_be(&mut self) -> Result<i32, BinaryError> {
+    self.schema("i32", |r| r.read_byte_array::<4>().map(i32::from_be_bytes))
+  }
+
+  /// Reads an i64 value from the buffer in big-endian order, regardless of the default byte order.
+  pub fn read_i64_be(&mut self) -> Result<i64, BinaryError> {
+    self.schema("i64", |r| r.read_byte_array::<8>().map(i64::from_be_bytes))
+  }
+
+  /// Reads a f32 value from the buffer in big-endian order, regardless of the default byte order.
+  pub fn read_f32_be(&mut self) -> Result<f32, BinaryError> {
+    self.schema("f32", |r| r.read_byte_array::<4>().map(f32::from_be_bytes))
+  }
+
+  /// Reads a f64 value from the buffer in big-endian order, regardless of the default byte order.
+  pub fn read_f64_be(&mut self) -> Result<f64, BinaryError> {
+    self.schema("f64", |r| r.read_byte_array::<8>().map(f64::from_be_bytes))
+  }
+
+  /// Reads a bool value from the buffer (expects 0 or 1). Any other byte is an `InvalidValue` error;
+  /// use `read_bool_lenient` for producers that write arbitrary nonzero values for true.
+  pub fn read_bool(&mut self) -> Result<bool, BinaryError> {
+    self.schema("bool", |r| {
+      match r.read_u8()? {
+        0 => Ok(false),
+        1 => Ok(true),
+        v => Err(BinaryError::InvalidValue(format!("invalid bool value {}", v))),
+      }
+    })
+  }
+
+  /// Reads a bool value from the buffer, treating 0 as false and any nonzero byte as true. Unlike
+  /// `read_bool` this never rejects a value, so a corrupted byte reads as `true` rather than erroring.
+  pub fn read_bool_lenient(&mut self) -> Result<bool, BinaryError> {
+    self.schema("bool", |r| r.read_u8().map(|v| v != 0))
+  }
+
+  /// Reads a char from the buffer. Expects a u32 code point and errors if it is not a valid Unicode scalar value.
+  pub fn read_char(&mut self) -> Result<char, BinaryError> {
+    self.schema("char", |r| {
+      let offset = r.cursor;
+      let value = r.read_u32()?;
+      char::from_u32(value).ok_or(BinaryError::InvalidChar { value, offset })
+    })
+  }
+
+  /// Reads a string from the buffer. Expects a u32 length followed by UTF-8 bytes.
+  pub fn read_string(&mut self) -> Result<String, BinaryError> {
+    self.schema("str", |r| r.read_string_with_prefix(PrefixWidth::U32))
+  }
+
+  /// Reads a string written by `write_string_utf16`: a little-endian u32 count of code units, then
+  /// that many UTF-16LE code units, whatever the reader's byte order. Errors with `InvalidChar` (at the unit's offset) on an unpaired surrogate.
+  pub fn read_string_utf16(&mut self) -> Result<String, BinaryError> {
+    self.schema("str", |r| {
+      let length = fit_usize(r.read_u32_le()? as u64, usize::MAX as u64)?;
+      let byte_len = r.checked_byte_len(length, 2)?;
+      r.ensure_available(byte_len)?;
+      let start = r.cursor;
+      let units = r.data[start..start + byte_len].chunks_exact(2).map(|unit| u16::from_le_bytes([unit[0], unit[1]]));
+      let mut value = String::with_capacity(length);
+      let mut offset = start;
+      for decoded in char::decode_utf16(units) {
+        match decoded {
+          Ok(c) => {
+            value.push(c);
+            offset += c.len_utf16() * 2;
+          }
+          Err(e) => return Err(BinaryError::InvalidChar { value: e.unpaired_surrogate() as u32, offset }),
+        }
+      }
+      r.cursor += byte_len;
+      Ok(value)
+    })
+  }
+
+  /// Fills `buf` with exactly `buf.len()` bytes from the buffer, like `std::io::Read::read_exact`.
+  /// Errors without advancing if fewer bytes remain.
+  pub fn read_into(&mut self, buf: &mut [u8]) -> Result<(), BinaryError> {
+    self.ensure_available(buf.len())?;
+    buf.copy_from_slice(&self.data[self.cursor..self.cursor + buf.len()]);
+    self.cursor += buf.len();
+    Ok(())
+  }
+
+  /// Returns a reader over the next `len` bytes, with the same byte order, and advances this reader
+  /// past them. The sub-reader cannot read beyond those bytes, so a sub-parser handed it cannot
+  /// over-read into the rest of the message.
+  pub fn take_reader(&mut self, len: usize) -> Result<BinaryReader<'a>, BinaryError> {
+    self.ensure_available(len)?;
+    let data = &self.data[self.cursor..self.cursor + len];
+    self.cursor += len;
+    Ok(BinaryReader::new_with_endianness(data, self.endianness))
+  }
+
+  /// Reads a frame written by `BinaryWriter::finish_framed`: a u32 byte count, then a reader bounded
+  /// to exactly that many bytes. Errors if the buffer is shorter than the declared count.
+  pub fn read_frame(&mut self) -> Result<BinaryReader<'a>, BinaryError> {
+    self.read_frame_with_prefix(PrefixWidth::U32)
+  }
+
+  /// Like `read_frame`, for a frame written by `finish_framed_with_prefix` with the given width.
+  pub fn read_frame_with_prefix(&mut self, prefix: PrefixWidth) -> Result<BinaryReader<'a>, BinaryError> {
+    self.try_read(|r| {
+      let length = r.read_length(prefix)?;
+      r.take_reader(length)
+    })
+  }
+
+  /// Reads a section written by `BinaryWriter::write_len_prefixed` with the same prefix width,
+  /// returning a reader scoped to exactly the declared length and advancing this reader past it.
+  pub fn read_len_prefixed(&mut self, prefix: PrefixWidth) -> Result<BinaryReader<'a>, BinaryError> {
+    self.read_frame_with_prefix(prefix)
+  }
+
+  /// Reads a message written by `BinaryWriter::write_message`, borrowing its bytes from the buffer.
+  pub fn read_message(&mut self) -> Result<&'a [u8], BinaryError> {
+    self.schema("vec", |r| r.read_prefixed_bytes(PrefixWidth::U32))
+  }
+
+  /// Returns an iterator over the remaining messages written by `BinaryWriter::write_message`. It
+  /// ends when the buffer is exhausted, or after yielding the error for a truncated message.
+  pub fn messages(&mut self) -> Messages<'_, 'a> {
+    Messages { reader: self, failed: false }
+  }
+
+  /// Reads the u16 format version written by `BinaryWriter::new_versioned`.
+  pub fn read_version(&mut self) -> Result<u16, BinaryError> {
+    self.read_u16()
+  }
+
+  /// Reads the format version like `read_version`, erroring with `UnsupportedVersion` unless it
+  /// lies in `min..=max`.
+  pub fn require_version(&mut self, min: u16, max: u16) -> Result<u16, BinaryError> {
+    let version = self.read_version()?;
+    if version < min || version > max {
+      return Err(BinaryError::UnsupportedVersion { version, min, max });
+    }
+    Ok(version)
+  }
+
+  /// Reads `expected.len()` bytes and checks that they equal `expected`, e.g. to validate a file's
+  /// magic number. The cursor only advances if they match.
+  pub fn expect_bytes(&mut self, expected: &[u8]) -> Result<(), BinaryError> {
+    self.ensure_available(expected.len())?;
+    let found = &self.data[self.cursor..self.cursor + expected.len()];
+    if found != expected {
+      return Err(BinaryError::MagicMismatch { expected: expected.to_vec(), found: found.to_vec() });
+    }
+    self.cursor += expected.len();
+    Ok(())
+  }
+
+  /// Reads a string whose length prefix has the given width, followed by UTF-8 bytes.
+  pub fn read_string_with_prefix(&mut self, prefix: PrefixWidth) -> Result<String, BinaryError> {
+    self.schema("str", |r| r.read_str_with_prefix(prefix).map(|s| s.to_string()))
+  }
+
+  /// Reads a string written by `write_string_varint`. The varint is decoded from at most 10 bytes,
+  /// and the length it gives is checked against the buffer before anything is copied.
+  pub fn read_string_varint(&mut self) -> Result<String, BinaryError> {
+    self.read_string_with_prefix(PrefixWidth::Varint)
+  }
+
+  /// Reads a string like `read_string`, but borrows it from the reader's buffer instead of
+  /// allocating a copy.
+  pub fn read_str(&mut self) -> Result<&'a str, BinaryError> {
+    self.schema("str", |r| r.read_str_with_prefix(PrefixWidth::U32))
+  }
+
+  /// Reads a string like `read_string` as a `Cow`, for code that also handles owned strings. The
+  /// reader holds its whole input in memory, so the result is always `Cow::Borrowed`.
+  pub fn read_cow_str(&mut self) -> Result<Cow<'a, str>, BinaryError> {
+    self.read_str().map(Cow::Borrowed)
+  }
+
+  /// Reads a string like `read_string`, but replaces invalid UTF-8 sequences with U+FFFD instead of
+  /// erroring. Use it for sources that are not fully trusted to be valid text, where showing a damaged
+  /// string beats rejecting the record; the bytes still have to be scanned, so it is no faster than
+  /// `read_string`, and the original bytes cannot be recovered from the result.
+  pub fn read_string_lossy(&mut self) -> Result<String, BinaryError> {
+    self.schema("str", |r| {
+      let bytes = r.read_prefixed_bytes(PrefixWidth::U32)?;
+      Ok(String::from_utf8_lossy(bytes).into_owned())
+    })
+  }
+
+  /// Reads a string whose length prefix has the given width, borrowing it from the buffer.
+  fn read_str_with_prefix(&mut self, prefix: PrefixWidth) -> Result<&'a str, BinaryError> {
+    let length = self.read_length(prefix)?;
+    self.read_str_of_len(length)
+  }
+
+  /// Borrows the next `length` bytes as UTF-8, advancing past them even if they are not valid UTF-8.
+  fn read_str_of_len(&mut self, length: usize) -> Result<&'a str, BinaryError> {
+    self.ensure_available(length)?;
+    let start = self.cursor;
+    let bytes = &self.data[start..start + length];
+    self.cursor += length;
+    std::str::from_utf8(bytes).map_err(|error| BinaryError::InvalidUtf8 { error, offset: start + error.valid_up_to() })
+  }
+
+  /// Reads a length prefix of the given width and borrows that many bytes from the buffer.
+  fn read_prefixed_bytes(&mut self, prefix: PrefixWidth) -> Result<&'a [u8], BinaryError> {
+    let length = self.read_length(prefix)?;
+    self.ensure_available(length)?;
+    let bytes = &self.data[self.cursor..self.cursor + length];
+    self.cursor += length;
+    Ok(bytes)
+  }
+
+  /// Reads up to `out.len()` u32 values (with no length prefix) into `out` and returns how many were
+  /// read. Fewer are read only when the buffer runs out, so repeated calls drain a long run of values.
+  pub fn read_u32_into(&mut self, out: &mut [u32]) -> Result<usize, BinaryError> {
+    let count = out.len().min(self.remaining() / 4);
+    for slot in &mut out[..count] {
+      *slot = self.read_u32()?;
+    }
+    Ok(count)
+  }
+
+  /// Fills every slot of `out` with a value read from the buffer (with no length prefix), or errors
+  /// if the buffer runs out. On error, the slots before the failing element have been overwritten.
+  pub fn read_exact_into<T: Readable>(&mut self, out: &mut [T]) -> Result<(), BinaryError> {
+    for slot in out {
+      *slot = T::read_from(self)?;
+    }
+    Ok(())
+  }
+
+  /// Reads a vector of u8 from the buffer. Expects a u32 length followed by bytes.
+  pub fn read_vec_u8(&mut self) -> Result<Vec<u8>, BinaryError> {
+    self.read_vec_u8_ref().map(<[u8]>::to_vec)
+  }
+
+  /// Reads a vector of u8 like `read_vec_u8`, but borrows the bytes from the buffer instead of
+  /// copying them, e.g. to forward or hash a large blob.
+  pub fn read_vec_u8_ref(&mut self) -> Result<&'a [u8], BinaryError> {
+    self.schema("vec", |r| r.read_prefixed_bytes(PrefixWidth::U32))
+  }
+
+  /// Reads a byte blob written by `write_bytes_u8len`: a u8 length followed by that many bytes.
+  pub fn read_bytes_u8len(&mut self) -> Result<Vec<u8>, BinaryError> {
+    self.schema("vec", |r| {
+      let length = r.read_u8()? as usize;
+      r.ensure_available(length)?;
+      let bytes = r.data[r.cursor..r.cursor + length].to_vec();
+      r.cursor += length;
+      Ok(bytes)
+    })
+  }
+
+  /// Reads a vector of u16 from the buffer. Expects a u32 length followed by u16 values.
+  pub fn read_vec_u16(&mut self) -> Result<Vec<u16>, BinaryError> {
+    self.schema("vec", |r| r.read_vec_fixed(u16::from_le_bytes))
+  }
+
+  /// Reads a vector of u32 from the buffer. Expects a u32 length followed by u32 values.
+  pub fn read_vec_u32(&mut self) -> Result<Vec<u32>, BinaryError> {
+    self.schema("vec", |r| r.read_vec_fixed(u32::from_le_bytes))
+  }
+
+  /// Reads a vector of u64 from the buffer. Expects a u32 length followed by u64 values.
+  pub fn read_vec_u64(&mut self) -> Result<Vec<u64>, BinaryError> {
+    self.schema("vec", |r| r.read_vec_fixed(u64::from_le_bytes))
+  }
+
+  /// Reads a vector of 24-bit unsigned values written by `write_vec_u24`.
+  pub fn read_vec_u24(&mut self) -> Result<Vec<u32>, BinaryError> {
+    self.schema("vec", |r| {
+      let length = r.read_count()?;
+      r.ensure_available(r.checked_byte_len(length, 3)?)?;
+      (0..length).map(|_| r.read_u24()).collect()
+    })
+  }
+
+  /// Reads a vector of 24-bit signed values written by `write_vec_i24`.
+  pub fn read_vec_i24(&mut self) -> Result<Vec<i32>, BinaryError> {
+    self.schema("vec", |r| {
+      let length = r.read_count()?;
+      r.ensure_available(r.checked_byte_len(length, 3)?)?;
+      (0..length).map(|_| r.read_i24()).collect()
+    })
+  }
+
+  /// Reads a vector of i8 from the buffer. Expects a u32 length followed by i8 values.
+  pub fn read_vec_i8(&mut self) -> Result<Vec<i8>, BinaryError> {
+    self.schema("vec", |r| Ok(r.read_prefixed_bytes(PrefixWidth::U32)?.iter().map(|&b| b as i8).collect()))
+  }
+
+  /// Reads a vector of i16 from the buffer. Expects a u32 length followed by i16 values.
+  pub fn read_vec_i16(&mut self) -> Result<Vec<i16>, BinaryError> {
+    self.schema("vec", |r| r.read_vec_fixed(i16::from_le_bytes))
+  }
+
+  /// Reads a vector of i32 from the buffer. Expects a u32 length followed by i32 values.
+  pub fn read_vec_i32(&mut self) -> Result<Vec<i32>, BinaryError> {
+    self.schema("vec", |r| r.read_vec_fixed(i32::from_le_bytes))
+  }
+
+  /// Reads a vector of i64 from the buffer. Expects a u32 length followed by i64 values.
+  pub fn read_vec_i64(&mut self) -> Result<Vec<i64>, BinaryError> {
+    self.schema("vec", |r| r.read_vec_fixed(i64::from_le_bytes))
+  }
+
+  /// Reads a vector of f32 from the buffer. Expects a u32 length followed by f32 values.
+  pub fn read_vec_f32(&mut self) -> Result<Vec<f32>, BinaryError> {
+    self.schema("vec", |r| r.read_vec_fixed(f32::from_le_bytes))
+  }
+
+  /// Reads a vector of f64 from the buffer. Expects a u32 length followed by f64 values.
+  pub fn read_vec_f64(&mut self) -> Result<Vec<f64>, BinaryError> {
+    self.schema("vec", |r| r.read_vec_fixed(f64::from_le_bytes))
+  }
+
+  /// Reads a vector of f32 with its length and values in little-endian order, regardless of the
+  /// default byte order.
+  pub fn read_vec_f32_le(&mut self) -> Result<Vec<f32>, BinaryError> {
+    self.schema("vec", |r| r.read_vec_fixed_in(Endianness::Little, f32::from_le_bytes))
+  }
+
+  /// Reads a vector of f64 with its length and values in little-endian order, regardless of the
+  /// default byte order.
+  pub fn read_vec_f64_le(&mut self) -> Result<Vec<f64>, BinaryError> {
+    self.schema("vec", |r| r.read_vec_fixed_in(Endianness::Little, f64::from_le_bytes))
+  }
+
+  /// Reads a vector of f32 with its length and values in big-endian order, regardless of the
+  /// default byte order, e.g. an array produced by a big-endian system.
+  pub fn read_vec_f32_be(&mut self) -> Result<Vec<f32>, BinaryError> {
+    self.schema("vec", |r| r.read_vec_fixed_in(Endianness::Big, f32::from_le_bytes))
+  }
+
+  /// Reads a vector of f64 with its length and values in big-endian order, regardless of the
+  /// default byte order, e.g. an array produced by a big-endian system.
+  pub fn read_vec_f64_be(&mut self) -> Result<Vec<f64>, BinaryError> {
+    self.schema("vec", |r| r.read_vec_fixed_in(Endianness::Big, f64::from_le_bytes))
+  }
+
+  /// Reads two parallel vectors written by `write_pairs_u32_f64`. Expects a u32 length followed by
+  /// that many u32 keys and then that many f64 values.
+  pub fn read_pairs_u32_f64(&mut self) -> Result<(Vec<u32>, Vec<f64>), BinaryError> {
+    self.schema("vec", |r| {
+      let length = r.read_count()?;
+      let keys = r.read_fixed_slice(length, u32::from_le_bytes)?;
+      let vals = r.read_fixed_slice(length, f64::from_le_bytes)?;
+      Ok((keys, vals))
+    })
+  }
+
+  /// Reads a vector of chars from the buffer. Expects a u32 length followed by u32 code points.
+  pub fn read_vec_char(&mut self) -> Result<Vec<char>, BinaryError> {
+    self.schema("vec", |r| r.read_vec_with_capacity_hint(DEFAULT_MAX_PREALLOC, Self::read_char))
+  }
+
+  /// Reads a vector of bools written by `write_vec_bool_rle`. Errors with `InvalidValue` if the runs
+  /// add up to more than the declared length. A few bytes of runs can claim billions of values, so
+  /// the declared length is capped at `DEFAULT_MAX_BOOL_RLE_LEN`; use `read_vec_bool_rle_bounded`
+  /// for a different limit.
+  pub fn read_vec_bool_rle(&mut self) -> Result<Vec<bool>, BinaryError> {
+    self.read_vec_bool_rle_bounded(DEFAULT_MAX_BOOL_RLE_LEN)
+  }
+
+  /// Reads a vector of bools like `read_vec_bool_rle`, erroring with `LengthOverflow` if its declared
+  /// length exceeds `max_len`.
+  pub fn read_vec_bool_rle_bounded(&mut self, max_len: usize) -> Result<Vec<bool>, BinaryError> {
+    self.schema("vec", |r| {
+      let total = fit_usize(r.read_varint()?, usize::MAX as u64)?;
+      if total > max_len {
+        return Err(BinaryError::LengthOverflow { length: total, max: max_len as u64 });
+      }
+      let mut bits = Vec::with_capacity(capacity_hint(total, DEFAULT_MAX_PREALLOC));
+      let mut current = false;
+      while bits.len() < total {
+        let run = r.read_varint()?;
+        if run > (total - bits.len()) as u64 {
+          return Err(BinaryError::InvalidValue(format!("bool runs exceed the declared length {}", total)));
+        }
+        bits.resize(bits.len() + run as usize, current);
+        current = !current;
+      }
+      Ok(bits)
+    })
+  }
+
+  /// Reads a vector of strings from the buffer. Expects a u32 length followed by serialized strings.
+  /// Every string needs at least its 4-byte length prefix, so a count or a string length that the
+  /// rest of the buffer cannot hold fails as soon as it is read, before any later string is decoded.
+  pub fn read_vec_string(&mut self) -> Result<Vec<String>, BinaryError> {
+    self.schema("vec", |r| {
+      let count = r.read_count()?;
+      r.ensure_available(r.checked_byte_len(count, 4)?)?;
+      let mut strings = Vec::with_capacity(count);
+      for index in 0..count {
+        let later_prefixes = r.checked_byte_len(count - index - 1, 4)?;
+        let string = r.schema("str", |r| {
+          let length = r.read_count()?;
+          let needed = length.checked_add(later_prefixes).ok_or_else(|| r.eof(usize::MAX))?;
+          r.ensure_available(needed)?;
+          r.read_str_of_len(length).map(str::to_string)
+        })?;
+        strings.push(string);
+      }
+      Ok(strings)
+    })
+  }
+
+  /// Reads a vector of byte blobs from the buffer. Expects a u32 count followed by each blob as
+  /// `read_vec_u8` reads it.
+  pub fn read_vec_bytes(&mut self) -> Result<Vec<Vec<u8>>, BinaryError> {
+    self.schema("vec", |r| r.read_vec_with_capacity_hint(DEFAULT_MAX_PREALLOC, Self::read_vec_u8))
+  }
+
+  /// Reads a vector of u8 like `read_vec_u8`, but errors with `LengthOverflow` if its declared
+  /// length exceeds `max_len`, before any element is read.
+  pub fn read_vec_u8_bounded(&mut self, max_len: usize) -> Result<Vec<u8>, BinaryError> {
+    self.schema("vec", |r| {
+      let length = r.read_bounded_count(max_len)?;
+      r.ensure_available(length)?;
+      let vec = r.data[r.cursor..r.cursor + length].to_vec();
+      r.cursor += length;
+      Ok(vec)
+    })
+  }
+
+  /// Reads a vector of u16 like `read_vec_u16`, erroring if its length exceeds `max_len`.
+  pub fn read_vec_u16_bounded(&mut self, max_len: usize) -> Result<Vec<u16>, BinaryError> {
+    self.schema("vec", |r| {
+      let length = r.read_bounded_count(max_len)?;
+      r.read_fixed_slice(length, u16::from_le_bytes)
+    })
+  }
+
+  /// Reads a vector of u32 like `read_vec_u32`, erroring if its length exceeds `max_len`.
+  pub fn read_vec_u32_bounded(&mut self, max_len: usize) -> Result<Vec<u32>, BinaryError> {
+    self.schema("vec", |r| {
+      let length = r.read_bounded_count(max_len)?;
+      r.read_fixed_slice(length, u32::from_le_bytes)
+    })
+  }
+
+  /// Reads a vector of u64 like `read_vec_u64`, erroring if its length exceeds `max_len`.
+  pub fn read_vec_u64_bounded(&mut self, max_len: usize) -> Result<Vec<u64>, BinaryError> {
+    self.schema("vec", |r| {
+      let length = r.read_bounded_count(max_len)?;
+      r.read_fixed_slice(length, u64::from_le_bytes)
+    })
+  }
+
+  /// Reads a vector of i32 like `read_vec_i32`, erroring if its length exceeds `max_len`.
+  pub fn read_vec_i32_bounded(&mut self, max_len: usize) -> Result<Vec<i32>, BinaryError> {
+    self.schema("vec", |r| {
+      let length = r.read_bounded_count(max_len)?;
+      r.read_fixed_slice(length, i32::from_le_bytes)
+    })
+  }
+
+  /// Reads a vector of i64 like `read_vec_i64`, erroring if its length exceeds `max_len`.
+  pub fn read_vec_i64_bounded(&mut self, max_len: usize) -> Result<Vec<i64>, BinaryError> {
+    self.schema("vec", |r| {
+      let length = r.read_bounded_count(max_len)?;
+      r.read_fixed_slice(length, i64::from_le_bytes)
+    })
+  }
+
+  /// Reads a vector of f32 like `read_vec_f32`, erroring if its length exceeds `max_len`.
+  pub fn read_vec_f32_bounded(&mut self, max_len: usize) -> Result<Vec<f32>, BinaryError> {
+    self.schema("vec", |r| {
+      let length = r.read_bounded_count(max_len)?;
+      r.read_fixed_slice(length, f32::from_le_bytes)
+    })
+  }
+
+  /// Reads a vector of f64 like `read_vec_f64`, erroring if its length exceeds `max_len`.
+  pub fn read_vec_f64_bounded(&mut self, max_len: usize) -> Result<Vec<f64>, BinaryError> {
+    self.schema("vec", |r| {
+      let length = r.read_bounded_count(max_len)?;
+      r.read_fixed_slice(length, f64::from_le_bytes)
+    })
+  }
+
+  /// Reads a vector of strings like `read_vec_string`, erroring if its length exceeds `max_len`.
+  pub fn read_vec_string_bounded(&mut self, max_len: usize) -> Result<Vec<String>, BinaryError> {
+    self.read_vec_bounded(max_len)
+  }
+
+  /// Reads a vector of any `Readable` type like `read_vec`, erroring if its length exceeds `max_len`.
+  pub fn read_vec_bounded<T: Readable>(&mut self, max_len: usize) -> Result<Vec<T>, BinaryError> {
+    self.schema("vec", |r| {
+      let length = r.read_bounded_count(max_len)?;
+      let mut vec = Vec::with_capacity(capacity_hint(length, DEFAULT_MAX_PREALLOC));
+      for _ in 0..length {
+        vec.push(T::read_from(r)?);
+      }
+      Ok(vec)
+    })
+  }
+
+  /// Reads a u32 element count, erroring with `LengthOverflow` if it exceeds `max_len`.
+  fn read_bounded_count(&mut self, max_len: usize) -> Result<usize, BinaryError> {
+    let length = self.read_count()?;
+    if length > max_len {
+      return Err(BinaryError::LengthOverflow { length, max: max_len as u64 });
+    }
+    Ok(length)
+  }
+
+  /// Returns the u32 length prefix of the next string or vector without advancing the cursor,
+  /// e.g. to size a progress bar or decide whether to skip the field.
+  pub fn peek_length(&self) -> Result<u32, BinaryError> {
+    self.peek_u32_at(0).ok_or_else(|| self.eof(4))
+  }
+
+  /// Returns how many bytes reading a `T` would consume, without advancing: the fixed size for
+  /// primitives, or for strings and vectors the total from their length prefixes, e.g. so a streaming
+  /// consumer can wait until that many bytes are buffered. The result may exceed `remaining()`.
+  /// Returns `None` if the prefixes needed have not arrived yet, or `T` does not report its size
+  /// (see `Readable::peek_size`).
+  pub fn next_read_size<T: Readable>(&self) -> Option<usize> {
+    T::peek_size(self, 0)
+  }
+
+  /// Returns the byte `offset` bytes past the cursor, if it is in the buffer.
+  fn peek_u8_at(&self, offset: usize) -> Option<u8> {
+    self.data.get(self.cursor.checked_add(offset)?).copied()
+  }
+
+  /// Returns the u32 in the reader's byte order `offset` bytes past the cursor, if it is in the buffer.
+  fn peek_u32_at(&self, offset: usize) -> Option<u32> {
+    let start = self.cursor.checked_add(offset)?;
+    let mut bytes: [u8; 4] = self.data.get(start..start.checked_add(4)?)?.try_into().unwrap();
+    if self.endianness == Endianness::Big {
+      bytes.reverse();
+    }
+    Some(u32::from_le_bytes(bytes))
+  }
+
+  /// Skips a string without decoding it. Reads the u32 length and advances past the bytes.
+  pub fn skip_string(&mut self) -> Result<(), BinaryError> {
+    let length = self.read_count()?;
+    self.advance(length)
+  }
+
+  /// Skips `count` bytes of padding written by `write_padding`. With `expect` set, every byte must
+  /// equal it, e.g. to validate a reserved region in a strict parser; on a mismatch the error gives
+  /// the offending byte's offset and the cursor does not move.
+  pub fn read_padding(&mut self, count: usize, expect: Option<u8>) -> Result<(), BinaryError> {
+    self.ensure_available(count)?;
+    if let Some(fill) = expect {
+      let region = &self.data[self.cursor..self.cursor + count];
+      if let Some(index) = region.iter().position(|&b| b != fill) {
+        return Err(BinaryError::InvalidValue(format!(
+          "padding byte at offset {} is {:#04x}, expected {:#04x}",
+          self.cursor + index,
+          region[index],
+          fill
+        )));
+      }
+    }
+    self.cursor += count;
+    Ok(())
+  }
+
+  /// Skips a vector of fixed-size primitives without decoding it. Reads the u32 length and advances
+  /// past `length * size_of::<T>()` bytes, so `T` must be a type whose wire size equals its in-memory
+  /// size (not `usize`/`isize`, which are always 8 bytes on the wire).
+  pub fn skip_vec<T>(&mut self) -> Result<(), BinaryError> {
+    let length = self.read_count()?;
+    let byte_len = self.checked_byte_len(length, std::mem::size_of::<T>())?;
+    self.advance(byte_len)
+  }
+
+  /// Reads a packed vector of strings written by `write_vec_string_packed` without copying it.
+  /// The offsets are validated up front, so every string can then be borrowed in O(1).
+  pub fn read_packed_strings(&mut self) -> Result<PackedStrings<'a>, BinaryError> {
+    self.schema("vec", |r| {
+      let count = r.read_count()?;
+      let offset_count = count.checked_add(1).ok_or_else(|| r.eof(usize::MAX))?;
+      let offsets = r.read_fixed_slice(offset_count, u32::from_le_bytes)?;
+      let blob_len = fit_usize(offsets[count] as u64, usize::MAX as u64)?;
+      r.ensure_available(blob_len)?;
+      let start = r.cursor;
+      let blob = std::str::from_utf8(&r.data[start..start + blob_len])
+        .map_err(|error| BinaryError::InvalidUtf8 { error, offset: start + error.valid_up_to() })?;
+      for (index, pair) in offsets.windows(2).enumerate() {
+        if pair[0] > pair[1] || !blob.is_char_boundary(pair[0] as usize) {
+          return Err(BinaryError::InvalidOffset { index });
+        }
+      }
+      r.cursor += blob_len;
+      Ok(PackedStrings { offsets, blob })
+    })
+  }
+
+  /// Reads the string table at the front of a buffer made by `BinaryWriter::finish_interned`. Pass
+  /// it to `read_interned` to resolve the indices that follow.
+  pub fn read_string_table(&mut self) -> Result<PackedStrings<'a>, BinaryError> {
+    self.read_packed_strings()
+  }
+
+  /// Reads a u32 index written by `BinaryWriter::write_interned` and returns the string it refers
+  /// to in `table`, borrowed from the buffer. Errors with `InvalidValue` if the index is out of range.
+  pub fn read_interned(&mut self, table: &PackedStrings<'a>) -> Result<&'a str, BinaryError> {
+    self.schema("str", |r| {
+      let index = r.read_u32()?;
+      table
+        .get(index as usize)
+        .ok_or_else(|| BinaryError::InvalidValue(format!("string index {} out of range for a table of {}", index, table.len())))
+    })
+  }
+
+  /// Reads a packed vector of strings written by `write_vec_string_packed` into owned strings.
+  pub fn read_vec_string_packed(&mut self) -> Result<Vec<String>, BinaryError> {
+    let packed = self.read_packed_strings()?;
+    Ok(packed.iter().map(|s| s.to_string()).collect())
+  }
+
+  /// Reads a tuple of `Readable` values in order, short-circuiting on the first error, e.g.
+  /// `let (id, name, active): (u32, String, bool) = reader.read_tuple()?;`.
+  pub fn read_tuple<T: Readable>(&mut self) -> Result<T, BinaryError> {
+    T::read_from(self)
+  }
+
+  /// Runs `f` as a single transactional read: if it fails, the cursor is rewound to where it was
+  /// before `f` started, so another interpretation of the same bytes can be tried.
+  pub fn try_read<T, F: FnOnce(&mut Self) -> Result<T, BinaryError>>(&mut self, f: F) -> Result<T, BinaryError> {
+    let start = self.checkpoint();
+    let result = f(self);
+    if result.is_err() {
+      self.restore(start);
+    }
+    result
+  }
+
+  /// Saves the current position, to jump back to later with `restore`, e.g. in a backtracking parser.
+  pub fn checkpoint(&self) -> Checkpoint {
+    Checkpoint {
+      buffer: (self.data.as_ptr() as usize, self.data.len()),
+      cursor: self.cursor,
+      #[cfg(feature = "debug-schema")]
+      schema_len: self.schema.entries.len(),
+    }
+  }
+
+  /// Moves the cursor back (or forward) to a position saved by `checkpoint`. Values recorded in the
+  /// schema trace after the checkpoint are dropped.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the checkpoint was taken on a reader over a different buffer.
+  pub fn restore(&mut self, checkpoint: Checkpoint) {
+    assert!(
+      checkpoint.buffer == (self.data.as_ptr() as usize, self.data.len()),
+      "checkpoint restored on a reader over a different buffer"
+    );
+    self.cursor = checkpoint.cursor;
+    #[cfg(feature = "debug-schema")]
+    self.schema.entries.truncate(checkpoint.schema_len);
+  }
+
+  /// Reads any value implementing `Readable` from the buffer.
+  pub fn read<T: Readable>(&mut self) -> Result<T, BinaryError> {
+    T::read_from(self)
+  }
+
+  /// Reads a map written as a u32 count followed by key/value pairs, inserting each pair into
+  /// `target` without clearing it, and returns the number of pairs read. On a key collision the
+  /// decoded value wins, both over an existing entry and over an earlier pair in the same map.
+  pub fn read_map_into<K: Readable + Eq + Hash, V: Readable, H: BuildHasher>(
+    &mut self,
+    target: &mut HashMap<K, V, H>,
+  ) -> Result<usize, BinaryError> {
+    if let Ok(length) = self.peek_length() {
+      target.reserve(capacity_hint(length as usize, DEFAULT_MAX_PREALLOC));
+    }
+    self.read_entries(|key, value| {
+      target.insert(key, value);
+    })
+  }
+
+  /// Reads a u32 count followed by that many key/value pairs, passing each pair to `insert`, and
+  /// returns the count. If both key and value are zero-sized, the count is capped at
+  /// `MAX_ZERO_SIZED_LEN`.
+  fn read_entries<K: Readable, V: Readable>(&mut self, mut insert: impl FnMut(K, V)) -> Result<usize, BinaryError> {
+    self.schema("map", |r| {
+      let length = r.read_count()?;
+      check_zero_sized_len::<(K, V)>(length)?;
+      for _ in 0..length {
+        let key = K::read_from(r)?;
+        let value = V::read_from(r)?;
+        insert(key, value);
+      }
+      Ok(length)
+    })
+  }
+
+  /// Reads an `Option` written by `write_option`, erroring with `InvalidValue` on a presence byte
+  /// other than 0 or 1.
+  pub fn read_option<T: Readable>(&mut self) -> Result<Option<T>, BinaryError> {
+    match self.read_u8()? {
+      0 => Ok(None),
+      1 => Ok(Some(T::read_from(self)?)),
+      tag => Err(BinaryError::InvalidValue(format!("invalid Option tag {}", tag))),
+    }
+  }
+
+  /// Reads a vector of options written by `write_vec_option_packed`. Errors with `InvalidValue` if
+  /// the bitmap's unused trailing bits are not zero.
+  pub fn read_vec_option_packed<T: Readable>(&mut self) -> Result<Vec<Option<T>>, BinaryError> {
+    self.schema("vec", |r| {
+      let length = r.read_count()?;
+      let bitmap_len = length.div_ceil(8);
+      r.ensure_available(bitmap_len)?;
+      let bitmap = &r.data[r.cursor..r.cursor + bitmap_len];
+      r.cursor += bitmap_len;
+      if length % 8 != 0 && bitmap[bitmap_len - 1] >> (length % 8) != 0 {
+        return Err(BinaryError::InvalidValue("unused bits set in option bitmap".to_string()));
+      }
+      let mut vec = Vec::with_capacity(capacity_hint(length, DEFAULT_MAX_PREALLOC));
+      for i in 0..length {
+        let present = bitmap[i / 8] & (1 << (i % 8)) != 0;
+        vec.push(if present { Some(T::read_from(r)?) } else { None });
+      }
+      Ok(vec)
+    })
+  }
+
+  /// Reads a `Result` written by `write_result`, erroring with `InvalidValue` on a tag other than
+  /// 0 or 1. The outer `Result` is the read itself; the inner one is the decoded value.
+  pub fn read_result<T: Readable, E: Readable>(&mut self) -> Result<Result<T, E>, BinaryError> {
+    match self.read_u8()? {
+      0 => Ok(Ok(T::read_from(self)?)),
+      1 => Ok(Err(E::read_from(self)?)),
+      tag => Err(BinaryError::InvalidValue(format!("invalid Result tag {}", tag))),
+    }
+  }
+
+  /// Reads a fixed-point decimal written by `write_fixed_point`, returning the scaled integer and
+  /// the scale. Errors with `InvalidValue` on a scale above 38.
+  pub fn read_fixed_point(&mut self) -> Result<(i64, u8), BinaryError> {
+    let value = self.read_i64()?;
+    let scale = self.read_u8()?;
+    check_scale(scale)?;
+    Ok((value, scale))
+  }
+
+  /// Reads a u32 of packed flags written by `write_flags`. Pass it to `FlagSet::from_bits` or
+  /// `decode_flags` to look the flags up by name.
+  pub fn read_flags(&mut self) -> Result<u32, BinaryError> {
+    self.read_u32()
+  }
+
+  /// Reads a `Range` written by `write_range`.
+  pub fn read_range<T: Readable>(&mut self) -> Result<Range<T>, BinaryError> {
+    let start = T::read_from(self)?;
+    let end = T::read_from(self)?;
+    Ok(start..end)
+  }
+
+  /// Reads a `RangeInclusive` written by `write_range_inclusive`.
+  pub fn read_range_inclusive<T: Readable>(&mut self) -> Result<RangeInclusive<T>, BinaryError> {
+    let start = T::read_from(self)?;
+    let end = T::read_from(self)?;
+    Ok(start..=end)
+  }
+
+  /// Reads a fixed-size array written by `write_array`: exactly `N` elements with no length prefix.
+  /// If an element fails to decode, the ones already read are dropped and the error returned.
+  pub fn read_array<T: Readable, const N: usize>(&mut self) -> Result<[T; N], BinaryError> {
+    let mut elements = Vec::with_capacity(N);
+    for _ in 0..N {
+      elements.push(T::read_from(self)?);
+    }
+    match elements.try_into() {
+      Ok(array) => Ok(array),
+      Err(_) => unreachable!("exactly N elements were read"),
+    }
+  }
+
+  /// Reads an IPv4 address written by `write_ipv4`: 4 octets in network order.
+  pub fn read_ipv4(&mut self) -> Result<Ipv4Addr, BinaryError> {
+    self.schema("ipv4", |r| r.read_byte_array::<4>().map(Ipv4Addr::from))
+  }
+
+  /// Reads an IPv6 address written by `write_ipv6`: 16 octets in network order.
+  pub fn read_ipv6(&mut self) -> Result<Ipv6Addr, BinaryError> {
+    self.schema("ipv6", |r| r.read_byte_array::<16>().map(Ipv6Addr::from))
+  }
+
+  /// Reads a set written as a u32 count followed by its elements. If `reject_duplicates` is set,
+  /// an element that appears more than once is an error instead of being merged.
+  pub fn read_set<T: Readable + Ord>(&mut self, reject_duplicates: bool) -> Result<BTreeSet<T>, BinaryError> {
+    let mut set = BTreeSet::new();
+    self.read_elements(reject_duplicates, |element| set.insert(element))?;
+    Ok(set)
+  }
+
+  /// Reads a set like `read_set`, into a `HashSet`.
+  pub fn read_hash_set<T: Readable + Eq + Hash>(&mut self, reject_duplicates: bool) -> Result<HashSet<T>, BinaryError> {
+    self.read_hash_set_with_hasher(reject_duplicates)
+  }
+
+  /// Reads a set like `read_hash_set`, into a `HashSet` with any default-constructible hasher.
+  fn read_hash_set_with_hasher<T: Readable + Eq + Hash, H: BuildHasher + Default>(
+    &mut self,
+    reject_duplicates: bool,
+  ) -> Result<HashSet<T, H>, BinaryError> {
+    let capacity = self.peek_length().map_or(0, |length| capacity_hint(length as usize, DEFAULT_MAX_PREALLOC));
+    let mut set = HashSet::with_capacity_and_hasher(capacity, H::default());
+    self.read_elements(reject_duplicates, |element| set.insert(element))?;
+    Ok(set)
+  }
+
+  /// Reads a u32 count followed by that many elements, passing each to `insert`, which returns
+  /// whether the element was new. A repeated element is an error if `reject_duplicates` is set.
+  fn read_elements<T: Readable>(&mut self, reject_duplicates: bool, mut insert: impl FnMut(T) -> bool) -> Result<(), BinaryError> {
+    self.schema("vec", |r| {
+      let length = r.read_count()?;
+      check_zero_sized_len::<T>(length)?;
+      for index in 0..length {
+        if !insert(T::read_from(r)?) && reject_duplicates {
+          return Err(BinaryError::DuplicateElement { index });
+        }
+      }
+      Ok(())
+    })
+  }
+
+  /// Reads a vector of any `Readable` type, including derived structs and enums. Expects a u32
+  /// length followed by that many elements. For types with a fixed `SERIALIZED_SIZE`, a length the
+  /// remaining bytes cannot hold fails before any element is decoded. Zero-sized elements take no
+  /// bytes, so their length is capped at `MAX_ZERO_SIZED_LEN` instead.
+  pub fn read_vec<T: Readable>(&mut self) -> Result<Vec<T>, BinaryError> {
+    self.schema("vec", |r| {
+      if let Some(size) = T::SERIALIZED_SIZE {
+        let length = fit_usize(r.peek_length()? as u64, usize::MAX as u64)?;
+        check_zero_sized_len::<T>(length)?;
+        let byte_len = r.checked_byte_len(length, size)?;
+        // The length prefix itself has not been consumed yet.
+        let (available, offset) = (r.remaining() - 4, r.cursor + 4);
+        if byte_len > available {
+          return Err(BinaryError::UnexpectedEof { needed: byte_len, available, offset });
+        }
+      }
+      r.read_vec_with_capacity_hint(DEFAULT_MAX_PREALLOC, T::read_from)
+    })
+  }
+
+  /// Reads a vector written by `write_vec_varint`, with the same overflow and allocation guards as
+  /// `read_string_varint` and `read_vec`.
+  pub fn read_vec_varint<T: Readable>(&mut self) -> Result<Vec<T>, BinaryError> {
+    self.read_vec_with_prefix(PrefixWidth::Varint)
+  }
+
+  /// Reads a vector written by `write_vec_with_prefix` with the same prefix width. Like `read_vec`,
+  /// caps the length of a vector of zero-sized elements at `MAX_ZERO_SIZED_LEN`.
+  pub fn read_vec_with_prefix<T: Readable>(&mut self, prefix: PrefixWidth) -> Result<Vec<T>, BinaryError> {
+    self.schema("vec", |r| {
+      let length = r.read_length(prefix)?;
+      check_zero_sized_len::<T>(length)?;
+      let mut vec = Vec::with_capacity(capacity_hint(length, DEFAULT_MAX_PREALLOC));
+      for _ in 0..length {
+        vec.push(T::read_from(r)?);
+      }
+      Ok(vec)
+    })
+  }
+
+  /// Reads a u32 length or element count and converts it to usize, erroring on targets whose usize
+  /// is too narrow to hold it instead of truncating.
+  fn read_count(&mut self) -> Result<usize, BinaryError> {
+    fit_usize(self.read_u32()? as u64, usize::MAX as u64)
+  }
+
+  /// Reads a length prefix of the given width.
+  fn read_length(&mut self, prefix: PrefixWidth) -> Result<usize, BinaryError> {
+    match prefix {
+      PrefixWidth::U8 => self.read_u8().map(|v| v as usize),
+      PrefixWidth::U16 => self.read_u16().map(|v| v as usize),
+      PrefixWidth::U32 => self.read_count(),
+      PrefixWidth::U64 => fit_usize(self.read_u64()?, usize::MAX as u64),
+      PrefixWidth::Varint => fit_usize(self.read_varint()?, usize::MAX as u64),
+    }
+  }
+
+  /// Advances the cursor past `size` bytes, erroring if fewer remain.
+  fn advance(&mut self, size: usize) -> Result<(), BinaryError> {
+    self.ensure_available(size)?;
+    self.cursor += size;
+    Ok(())
+  }
+
+  /// Reads `N` bytes in the reader's byte order and returns them in little-endian order.
+  fn read_ordered<const N: usize>(&mut self) -> Result<[u8; N], BinaryError> {
+    let mut bytes = self.read_byte_array::<N>()?;
+    if self.endianness == Endianness::Big {
+      bytes.reverse();
+    }
+    Ok(bytes)
+  }
+
+  /// Reads exactly `N` bytes from the buffer into an array.
+  fn read_byte_array<const N: usize>(&mut self) -> Result<[u8; N], BinaryError> {
+    self.ensure_available(N)?;
+    let bytes = self.data[self.cursor..self.cursor + N].try_into().unwrap();
+    self.cursor += N;
+    Ok(bytes)
+  }
+
+  /// Reads a u32 length followed by that many elements decoded by `read_element`. At most
+  /// `max_prealloc` slots are reserved up front; past that the vector grows as elements are
+  /// actually decoded, so a bogus length prefix cannot force a huge allocation.
+  pub fn read_vec_with_capacity_hint<T, F>(&mut self, max_prealloc: usize, mut read_element: F) -> Result<Vec<T>, BinaryError>
+  where
+    F: FnMut(&mut Self) -> Result<T, BinaryError>,
+  {
+    let length = self.read_count()?;
+    let mut vec = Vec::with_capacity(capacity_hint(length, max_prealloc));
+    for _ in 0..length {
+      vec.push(read_element(self)?);
+    }
+    Ok(vec)
+  }
+
+  /// Reads a u32 length followed by that many `N`-byte values in the reader's byte order. The whole
+  /// payload is bounds-checked once and decoded in bulk.
+  fn read_vec_fixed<T, const N: usize>(&mut self, from_le: fn([u8; N]) -> T) -> Result<Vec<T>, BinaryError> {
+    let length = self.read_count()?;
+    self.read_fixed_slice(length, from_le)
+  }
+
+  /// Like `read_vec_fixed`, but in `endianness` instead of the reader's byte order.
+  fn read_vec_fixed_in<T, const N: usize>(&mut self, endianness: Endianness, from_le: fn([u8; N]) -> T) -> Result<Vec<T>, BinaryError> {
+    let saved = std::mem::replace(&mut self.endianness, endianness);
+    let result = self.read_vec_fixed(from_le);
+    self.endianness = saved;
+    result
+  }
+
+  /// Reads `length` `N`-byte values in the reader's byte order with no length prefix, decoded in bulk.
+  /// When that order matches the host's, the bytes already have `T`'s memory layout and are copied
+  /// straight into the vector; otherwise each value is byte-swapped and passed to `from_le`.
+  fn read_fixed_slice<T, const N: usize>(&mut self, length: usize, from_le: fn([u8; N]) -> T) -> Result<Vec<T>, BinaryError> {
+    debug_assert_eq!(std::mem::size_of::<T>(), N);
+    let byte_len = self.checked_byte_len(length, N)?;
+    self.ensure_available(byte_len)?;
+    let bytes = &self.data[self.cursor..self.cursor + byte_len];
+    self.cursor += byte_len;
+    if self.endianness == Endianness::NATIVE {
+      let mut vec = Vec::<T>::with_capacity(length);
+      // SAFETY: `T` is only ever a primitive integer or float of exactly `N` bytes, for which every
+      // bit pattern is valid, so `byte_len` bytes initialize exactly `length` elements. The source
+      // is a byte slice and the destination a fresh allocation, so they cannot overlap.
+      unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), vec.as_mut_ptr() as *mut u8, byte_len);
+        vec.set_len(length);
+      }
+      return Ok(vec);
+    }
+    let reverse = self.endianness == Endianness::Big;
+    Ok(
+      bytes
+        .chunks_exact(N)
+        .map(|chunk| {
+          let mut array: [u8; N] = chunk.try_into().unwrap();
+          if reverse {
+            array.reverse();
+          }
+          from_le(array)
+        })
+        .collect(),
+    )
+  }
+
+  /// Returns the (type name, byte offset) of each value read so far, in order. Compare it with the
+  /// writer's `BinaryWriter::schema_trace` to find the first field where the two sides disagree.
+  #[cfg(feature = "debug-schema")]
+  pub fn schema_trace(&self) -> Vec<(&'static str, usize)> {
+    self.schema.entries.clone()
+  }
+
+  /// Runs `read`, first recording `name` at the current offset in the schema trace unless this is
+  /// nested inside another recorded read.
+  #[cfg(feature = "debug-schema")]
+  fn schema<T>(&mut self, name: &'static str, read: impl FnOnce(&mut Self) -> Result<T, BinaryError>) -> Result<T, BinaryError> {
+    let nested = std::mem::replace(&mut self.schema.nested, true);
+    if !nested {
+      self.schema.entries.push((name, self.cursor));
+    }
+    let result = read(self);
+    self.schema.nested = nested;
+    result
+  }
+
+  /// Runs `read`. Without the `debug-schema` feature there is no trace to record into.
+  #[cfg(not(feature = "debug-schema"))]
+  #[inline(always)]
+  fn schema<T>(&mut self, _name: &'static str, read: impl FnOnce(&mut Self) -> Result<T, BinaryError>) -> Result<T, BinaryError> {
+    read(self)
+  }
+
+  /// Ensures that there are at least `size` bytes available to read. The end position is computed
+  /// with `checked_add`, so a hostile `size` near `usize::MAX` (easily reached from a u32 length on a
+  /// 32-bit target) fails the check instead of wrapping around and passing it. Callers may then
+  /// index `cursor..cursor + size` without overflow.
+  fn ensure_available(&self, size: usize) -> Result<(), BinaryError> {
+    match self.cursor.checked_add(size) {
+      Some(end) if end <= self.data.len() => Ok(()),
+      _ => Err(self.eof(size)),
+    }
+  }
+
+  /// The error for a read of `needed` bytes at the cursor that the rest of the buffer cannot satisfy.
+  fn eof(&self, needed: usize) -> BinaryError {
+    BinaryError::UnexpectedEof { needed, available: self.remaining(), offset: self.cursor }
+  }
+
+  /// Returns the byte length of `count` elements of `elem_size` bytes each. A product that overflows
+  /// usize cannot fit in any buffer, so it is reported as `UnexpectedEof` (needing `usize::MAX`
+  /// bytes) rather than wrapping around to a small length that would pass the bounds check.
+  fn checked_byte_len(&self, count: usize, elem_size: usize) -> Result<usize, BinaryError> {
+    count.checked_mul(elem_size).ok_or_else(|| self.eof(usize::MAX))
+  }
+}
+
+/// PackedStrings borrows a packed string vector from a reader's buffer, giving O(1) access by index.
+#[derive(Debug, Clone)]
+pub struct PackedStrings<'a> {
+  offsets: Vec<u32>,
+  blob: &'a str,
+}
+
+impl<'a> PackedStrings<'a> {
+  /// Returns the number of strings.
+  pub fn len(&self) -> usize {
+    self.offsets.len() - 1
+  }
+
+  /// Returns true if there are no strings.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Returns the string at `index`, or `None` if it is out of bounds.
+  pub fn get(&self, index: usize) -> Option<&'a str> {
+    let start = *self.offsets.get(index)? as usize;
+    let end = *self.offsets.get(index + 1)? as usize;
+    Some(&self.blob[start..end])
+  }
+
+  /// Returns an iterator over the strings in order.
+  pub fn iter(&self) -> impl Iterator<Item = &'a str> + '_ {
+    (0..self.len()).map(move |i| self.get(i).unwrap())
+  }
+}
+
+/// StringInterner collects the distinct strings of a message for `BinaryWriter::write_interned`,
+/// giving each one a stable u32 index in first-seen order. `BinaryWriter::finish_interned` writes
+/// them as a table ahead of the message.
+#[derive(Debug, Clone, Default)]
+pub struct StringInterner {
+  strings: Vec<String>,
+  indices: HashMap<String, u32>,
+}
+
+impl StringInterner {
+  /// Creates an empty interner.
+  pub fn new() -> Self {
+    StringInterner::default()
+  }
+
+  /// Returns the index of `s`, adding it to the table if it has not been seen before.
+  pub fn intern(&mut self, s: &str) -> u32 {
+    if let Some(&index) = self.indices.get(s) {
+      return index;
+    }
+    let index = self.strings.len() as u32;
+    self.strings.push(s.to_string());
+    self.indices.insert(s.to_string(), index);
+    index
+  }
+
+  /// Returns the distinct strings interned so far, in index order.
+  pub fn strings(&self) -> &[String] {
+    &self.strings
+  }
+
+  /// Returns the number of distinct strings.
+  pub fn len(&self) -> usize {
+    self.strings.len()
+  }
+
+  /// Returns true if no strings have been interned.
+  pub fn is_empty(&self) -> bool {
+    self.strings.is_empty()
+  }
+}
+
+/// Messages iterates over the length-prefixed messages of a reader, created by `BinaryReader::messages`.
+#[derive(Debug)]
+pub struct Messages<'r, 'a> {
+  reader: &'r mut BinaryReader<'a>,
+  failed: bool,
+}
+
+impl<'a> Iterator for Messages<'_, 'a> {
+  type Item = Result<&'a [u8], BinaryError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.failed || self.reader.remaining() == 0 {
+      return None;
+    }
+    let message = self.reader.read_message();
+    self.failed = message.is_err();
+    Some(message)
+  }
+}
+
+/// Checkpoint is a saved reader position, created by `BinaryReader::checkpoint` and passed back to
+/// `BinaryReader::restore`. It cannot be built from an arbitrary offset, and remembers which buffer
+/// it belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+  buffer: (usize, usize),
+  cursor: usize,
+  #[cfg(feature = "debug-schema")]
+  schema_len: usize,
+}
+
+/// FrameDecoder reassembles length-prefixed frames (as written by `BinaryWriter::finish_framed` or
+/// `write_message`) from bytes that arrive in arbitrary pieces, e.g. reads from a socket. Bytes are
+/// buffered by `push` until a whole frame, prefix included, is available from `next_frame`.
+#[derive(Debug, Clone)]
+pub struct FrameDecoder {
+  buffer: Vec<u8>,
+  prefix: PrefixWidth,
+  endianness: Endianness,
+  /// The decoded prefix of the frame at the front of the buffer, as (prefix bytes, payload length),
+  /// once all of the prefix has arrived.
+  pending: Option<(usize, usize)>,
+}
+
+impl FrameDecoder {
+  /// Creates a decoder for frames with a little-endian u32 byte count prefix.
+  pub fn new() -> Self {
+    FrameDecoder::with_prefix(PrefixWidth::U32, Endianness::Little)
+  }
+
+  /// Creates a decoder for frames written by `finish_framed_with_prefix` with the given prefix
+  /// width and byte order.
+  pub fn with_prefix(prefix: PrefixWidth, endianness: Endianness) -> Self {
+    FrameDecoder { buffer: Vec::new(), prefix, endianness, pending: None }
+  }
+
+  /// Appends received bytes. They may end anywhere, including in the middle of a length prefix.
+  pub fn push(&mut self, bytes: &[u8]) {
+    self.buffer.extend_from_slice(bytes);
+  }
+
+  /// Returns the payload of the next complete frame and drops it from the buffer, or `Ok(None)`
+  /// until enough bytes have been pushed. The prefix is decoded once, as soon as it is complete.
+  /// A malformed prefix (an overlong varint, or a length too large for this target's usize) is an
+  /// error, and stays one on later calls: the stream cannot be resynchronized.
+  pub fn next_frame(&mut self) -> Result<Option<Vec<u8>>, BinaryError> {
+    let (header, length) = match self.pending {
+      Some(pending) => pending,
+      None => {
+        let mut reader = BinaryReader::new_with_endianness(&self.buffer, self.endianness);
+        let length = match reader.read_length(self.prefix) {
+          Ok(length) => length,
+          Err(BinaryError::UnexpectedEof { .. }) => return Ok(None),
+          Err(e) => return Err(e),
+        };
+        let pending = (self.buffer.len() - reader.remaining(), length);
+        self.pending = Some(pending);
+        pending
+      }
+    };
+    if self.buffer.len() - header < length {
+      return Ok(None);
+    }
+    let payload = self.buffer[header..header + length].to_vec();
+    self.buffer.drain(..header + length);
+    self.pending = None;
+    Ok(Some(payload))
+  }
+
+  /// Returns the number of buffered bytes not yet returned as frames.
+  pub fn buffered(&self) -> usize {
+    self.buffer.len()
+  }
+}
+
+impl Default for FrameDecoder {
+  fn default() -> Self {
+    FrameDecoder::new()
+  }
+}
+
+/// FlagSet is a u32 of boolean flags addressed by name, with names mapped to bit positions by a
+/// table such as `&[("compressed", 0), ("encrypted", 1)]`. Serialize it with
+/// `BinaryWriter::write_flags(set.bits())` and rebuild it with `FlagSet::from_bits`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlagSet<'n> {
+  names: &'n [(&'n str, u32)],
+  bits: u32,
+}
+
+impl<'n> FlagSet<'n> {
+  /// Creates a set with every flag cleared.
+  pub fn new(names: &'n [(&'n str, u32)]) -> Self {
+    FlagSet::from_bits(names, 0)
+  }
+
+  /// Creates a set from packed bits, e.g. as returned by `BinaryReader::read_flags`.
+  pub fn from_bits(names: &'n [(&'n str, u32)], bits: u32) -> Self {
+    FlagSet { names, bits }
+  }
+
+  /// Returns the packed bits.
+  pub fn bits(&self) -> u32 {
+    self.bits
+  }
+
+  /// Sets or clears the flag called `name`, erroring with `InvalidValue` if the table has no such flag.
+  pub fn set(&mut self, name: &str, on: bool) -> Result<&mut Self, BinaryError> {
+    let mask = self.mask(name).ok_or_else(|| BinaryError::InvalidValue(format!("unknown flag {:?}", name)))?;
+    if on {
+      self.bits |= mask;
+    } else {
+      self.bits &= !mask;
+    }
+    Ok(self)
+  }
+
+  /// Returns true if the flag called `name` is set. An unknown name is never set.
+  pub fn is_set(&self, name: &str) -> bool {
+    self.mask(name).is_some_and(|mask| self.bits & mask != 0)
+  }
+
+  /// Returns the names of the set flags, in table order.
+  pub fn names(&self) -> Vec<&'n str> {
+    decode_flags(self.bits, self.names)
+  }
+
+  fn mask(&self, name: &str) -> Option<u32> {
+    let &(_, position) = self.names.iter().find(|(n, _)| *n == name)?;
+    1u32.checked_shl(position)
+  }
+}
+
+/// Returns the names in `names` (a table of name and bit position) whose bits are set in `bits`,
+/// in table order. Bits with no name are ignored.
+pub fn decode_flags<'n>(bits: u32, names: &[(&'n str, u32)]) -> Vec<&'n str> {
+  names
+    .iter()
+    .filter(|&&(_, position)| 1u32.checked_shl(position).is_some_and(|mask| bits & mask != 0))
+    .map(|&(name, _)| name)
+    .collect()
+}
+
+/// Writable is implemented by types that can be serialized with a BinaryWriter.
+pub trait Writable {
+  /// Writes this value to the writer's buffer.
+  fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>);
+}
+
+/// Readable is implemented by types that can be deserialized with a BinaryReader.
+pub trait Readable: Sized {
+  /// The exact number of bytes every value of this type occupies when serialized, or `None` if the
+  /// size depends on the value (e.g. strings and vectors).
+  const SERIALIZED_SIZE: Option<usize> = None;
+
+  /// Reads a value of this type from the reader's buffer.
+  fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError>;
+
+  /// Returns the number of bytes a value of this type starting `offset` bytes past the reader's
+  /// cursor would take to read, without advancing. Variable-size types peek at their length prefixes;
+  /// `None` means the size cannot be told from the bytes buffered so far. The default is
+  /// `SERIALIZED_SIZE`, so only variable-size types need to override it.
+  fn peek_size(_reader: &BinaryReader<'_>, _offset: usize) -> Option<usize> {
+    Self::SERIALIZED_SIZE
+  }
+}
+
+/// Returns the total `peek_size` of `count` consecutive values of `T` starting at `offset`.
+fn peek_elements<T: Readable>(reader: &BinaryReader<'_>, offset: usize, count: usize) -> Option<usize> {
+  if let Some(size) = T::SERIALIZED_SIZE {
+    return count.checked_mul(size);
+  }
+  let mut total = 0usize;
+  for _ in 0..count {
+    total = total.checked_add(T::peek_size(reader, offset.checked_add(total)?)?)?;
+  }
+  Some(total)
+}
+
+/// SizeHint is implemented by types that can report their exact serialized size up front, so a
+/// buffer can be allocated once with `BinaryWriter::with_capacity`.
+pub trait SizeHint {
+  /// Returns the number of bytes `Writable::write_to` writes for this value.
+  fn serialized_size(&self) -> usize;
+}
+
+macro_rules! impl_primitive {
+  ($($ty:ty => $write:ident, $read:ident, $size:expr;)*) => {
+    $(
+      impl Writable for $ty {
+        fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+          writer.$write(*self);
+        }
+      }
+
+      impl Readable for $ty {
+        const SERIALIZED_SIZE: Option<usize> = Some($size);
+
+        fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+          reader.$read()
+        }
+      }
+
+      impl SizeHint for $ty {
+        fn serialized_size(&self) -> usize {
+          $size
+        }
+      }
+    )*
+  };
+}
+
+impl_primitive! {
+  u8 => write_u8, read_u8, 1;
+  u16 => write_u16, read_u16, 2;
+  u32 => write_u32, read_u32, 4;
+  u64 => write_u64, read_u64, 8;
+  i8 => write_i8, read_i8, 1;
+  i16 => write_i16, read_i16, 2;
+  i32 => write_i32, read_i32, 4;
+  i64 => write_i64, read_i64, 8;
+  usize => write_usize, read_usize, 8;
+  isize => write_isize, read_isize, 8;
+  f32 => write_f32, read_f32, 4;
+  f64 => write_f64, read_f64, 8;
+  bool => write_bool, read_bool, 1;
+  char => write_char, read_char, 4;
+}
+
+impl SizeHint for () {
+  fn serialized_size(&self) -> usize {
+    0
+  }
+}
+
+impl SizeHint for str {
+  fn serialized_size(&self) -> usize {
+    4 + self.len()
+  }
+}
+
+impl SizeHint for String {
+  fn serialized_size(&self) -> usize {
+    4 + self.len()
+  }
+}
+
+impl<T: SizeHint> SizeHint for [T] {
+  fn serialized_size(&self) -> usize {
+    4 + self.iter().map(T::serialized_size).sum::<usize>()
+  }
+}
+
+impl<T: SizeHint> SizeHint for Vec<T> {
+  fn serialized_size(&self) -> usize {
+    self.as_slice().serialized_size()
+  }
+}
+
+impl<T: SizeHint> SizeHint for BTreeSet<T> {
+  fn serialized_size(&self) -> usize {
+    4 + self.iter().map(T::serialized_size).sum::<usize>()
+  }
+}
+
+impl<T: SizeHint, H> SizeHint for HashSet<T, H> {
+  fn serialized_size(&self) -> usize {
+    4 + self.iter().map(T::serialized_size).sum::<usize>()
+  }
+}
+
+impl<K: SizeHint, V: SizeHint> SizeHint for BTreeMap<K, V> {
+  fn serialized_size(&self) -> usize {
+    4 + self.iter().map(|(k, v)| k.serialized_size() + v.serialized_size()).sum::<usize>()
+  }
+}
+
+impl<K: SizeHint, V: SizeHint, H> SizeHint for HashMap<K, V, H> {
+  fn serialized_size(&self) -> usize {
+    4 + self.iter().map(|(k, v)| k.serialized_size() + v.serialized_size()).sum::<usize>()
+  }
+}
+
+impl<B: SizeHint + ToOwned + ?Sized> SizeHint for Cow<'_, B> {
+  fn serialized_size(&self) -> usize {
+    (**self).serialized_size()
+  }
+}
+
+impl Writable for () {
+  fn write_to<S: Sink>(&self, _writer: &mut BinaryWriter<S>) {}
+}
+
+impl Readable for () {
+  const SERIALIZED_SIZE: Option<usize> = Some(0);
+
+  fn read_from(_reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+    Ok(())
+  }
+}
+
+impl Writable for str {
+  fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+    writer.write_string(self);
+  }
+}
+
+impl Writable for String {
+  fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+    writer.write_string(self);
+  }
+}
+
+impl Readable for String {
+  fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+    reader.read_string()
+  }
+
+  fn peek_size(reader: &BinaryReader<'_>, offset: usize) -> Option<usize> {
+    (reader.peek_u32_at(offset)? as usize).checked_add(4)
+  }
+}
+
+impl<T: Writable> Writable for [T] {
+  fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+    writer.write_vec(self);
+  }
+}
+
+impl<T: Writable> Writable for Vec<T> {
+  fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+    writer.write_vec(self);
+  }
+}
+
+impl<T: Readable> Readable for Vec<T> {
+  fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+    reader.read_vec()
+  }
+
+  fn peek_size(reader: &BinaryReader<'_>, offset: usize) -> Option<usize> {
+    let count = reader.peek_u32_at(offset)? as usize;
+    peek_elements::<T>(reader, offset.checked_add(4)?, count)?.checked_add(4)
+  }
+}
+
+impl<T: Writable, const N: usize> Writable for [T; N] {
+  fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+    writer.write_array(self);
+  }
+}
+
+impl<T: Readable, const N: usize> Readable for [T; N] {
+  const SERIALIZED_SIZE: Option<usize> = match T::SERIALIZED_SIZE {
+    Some(size) => Some(size * N),
+    None => None,
+  };
+
+  fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+    reader.read_array()
+  }
+
+  fn peek_size(reader: &BinaryReader<'_>, offset: usize) -> Option<usize> {
+    peek_elements::<T>(reader, offset, N)
+  }
+}
+
+impl<T: SizeHint, const N: usize> SizeHint for [T; N] {
+  fn serialized_size(&self) -> usize {
+    self.iter().map(T::serialized_size).sum()
+  }
+}
+
+// A marker carries no data, so it is written as nothing and read back without consuming bytes.
+impl<T: ?Sized> Writable for PhantomData<T> {
+  fn write_to<S: Sink>(&self, _writer: &mut BinaryWriter<S>) {}
+}
+
+impl<T: ?Sized> Readable for PhantomData<T> {
+  const SERIALIZED_SIZE: Option<usize> = Some(0);
+
+  fn read_from(_reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+    Ok(PhantomData)
+  }
+}
+
+impl<T: ?Sized> SizeHint for PhantomData<T> {
+  fn serialized_size(&self) -> usize {
+    0
+  }
+}
+
+impl<T: Writable> Writable for Option<T> {
+  fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+    writer.write_option(self);
+  }
+}
+
+impl<T: Readable> Readable for Option<T> {
+  fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+    reader.read_option()
+  }
+
+  fn peek_size(reader: &BinaryReader<'_>, offset: usize) -> Option<usize> {
+    match reader.peek_u8_at(offset)? {
+      0 => Some(1),
+      1 => T::peek_size(reader, offset.checked_add(1)?)?.checked_add(1),
+      _ => None,
+    }
+  }
+}
+
+impl<T: SizeHint> SizeHint for Option<T> {
+  fn serialized_size(&self) -> usize {
+    1 + self.as_ref().map_or(0, T::serialized_size)
+  }
+}
+
+impl<T: Writable, E: Writable> Writable for Result<T, E> {
+  fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+    writer.write_result(self);
+  }
+}
+
+impl<T: Readable, E: Readable> Readable for Result<T, E> {
+  fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+    reader.read_result()
+  }
+}
+
+impl<T: SizeHint, E: SizeHint> SizeHint for Result<T, E> {
+  fn serialized_size(&self) -> usize {
+    1 + match self {
+      Ok(v) => v.serialized_size(),
+      Err(e) => e.serialized_size(),
+    }
+  }
+}
+
+/// The size of two values of a type with the given `SERIALIZED_SIZE`, for the range impls.
+const fn pair_size(size: Option<usize>) -> Option<usize> {
+  match size {
+    Some(size) => Some(size * 2),
+    None => None,
+  }
+}
+
+impl<T: Writable> Writable for Range<T> {
+  fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+    writer.write_range(self);
+  }
+}
+
+impl<T: Readable> Readable for Range<T> {
+  const SERIALIZED_SIZE: Option<usize> = pair_size(T::SERIALIZED_SIZE);
+
+  fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+    reader.read_range()
+  }
+}
+
+impl<T: SizeHint> SizeHint for Range<T> {
+  fn serialized_size(&self) -> usize {
+    self.start.serialized_size() + self.end.serialized_size()
+  }
+}
+
+impl<T: Writable> Writable for RangeInclusive<T> {
+  fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+    writer.write_range_inclusive(self);
+  }
+}
+
+impl<T: Readable> Readable for RangeInclusive<T> {
+  const SERIALIZED_SIZE: Option<usize> = pair_size(T::SERIALIZED_SIZE);
+
+  fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+    reader.read_range_inclusive()
+  }
+}
+
+impl<T: SizeHint> SizeHint for RangeInclusive<T> {
+  fn serialized_size(&self) -> usize {
+    self.start().serialized_size() + self.end().serialized_size()
+  }
+}
+
+impl Writable for Ipv4Addr {
+  fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+    writer.write_ipv4(*self);
+  }
+}
+
+impl Readable for Ipv4Addr {
+  const SERIALIZED_SIZE: Option<usize> = Some(4);
+
+  fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+    reader.read_ipv4()
+  }
+}
+
+impl SizeHint for Ipv4Addr {
+  fn serialized_size(&self) -> usize {
+    4
+  }
+}
+
+impl Writable for Ipv6Addr {
+  fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+    writer.write_ipv6(*self);
+  }
+}
+
+impl Readable for Ipv6Addr {
+  const SERIALIZED_SIZE: Option<usize> = Some(16);
+
+  fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+    reader.read_ipv6()
+  }
+}
+
+impl SizeHint for Ipv6Addr {
+  fn serialized_size(&self) -> usize {
+    16
+  }
+}
+
+impl<K: Writable, V: Writable, H> Writable for HashMap<K, V, H> {
+  fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+    writer.write_entries(self.len(), self.iter());
+  }
+}
+
+impl<K: Readable + Eq + Hash, V: Readable, H: BuildHasher + Default> Readable for HashMap<K, V, H> {
+  fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+    let mut map = HashMap::default();
+    reader.read_map_into(&mut map)?;
+    Ok(map)
+  }
+}
+
+impl<T: Writable + Ord> Writable for BTreeSet<T> {
+  fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+    writer.write_set(self);
+  }
+}
+
+impl<T: Readable + Ord> Readable for BTreeSet<T> {
+  fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+    reader.read_set(false)
+  }
+}
+
+impl<T: Writable, H> Writable for HashSet<T, H> {
+  fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+    writer.write_elements(self.len(), self.iter());
+  }
+}
+
+impl<T: Readable + Eq + Hash, H: BuildHasher + Default> Readable for HashSet<T, H> {
+  fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+    reader.read_hash_set_with_hasher(false)
+  }
+}
+
+// Unlike `HashMap`, a `BTreeMap` writes its entries in key order, so equal maps encode identically.
+impl<K: Writable, V: Writable> Writable for BTreeMap<K, V> {
+  fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+    writer.write_entries(self.len(), self.iter());
+  }
+}
+
+impl<K: Readable + Ord, V: Readable> Readable for BTreeMap<K, V> {
+  fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+    let mut map = BTreeMap::new();
+    reader.read_entries(|key, value| {
+      map.insert(key, value);
+    })?;
+    Ok(map)
+  }
+}
+
+impl<B: Writable + ToOwned + ?Sized> Writable for Cow<'_, B> {
+  fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+    (**self).write_to(writer);
+  }
+}
+
+// Decoded values are always owned, so reading yields `Cow::Owned`.
+impl<B: ToOwned + ?Sized> Readable for Cow<'_, B>
+where
+  B::Owned: Readable,
+{
+  const SERIALIZED_SIZE: Option<usize> = B::Owned::SERIALIZED_SIZE;
+
+  fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+    B::Owned::read_from(reader).map(Cow::Owned)
+  }
+
+  fn peek_size(reader: &BinaryReader<'_>, offset: usize) -> Option<usize> {
+    B::Owned::peek_size(reader, offset)
+  }
+}
+
+macro_rules! impl_tuple {
+  ($(($($name:ident),+))*) => {
+    $(
+      impl<$($name: Writable),+> Writable for ($($name,)+) {
+        #[allow(non_snake_case)]
+        fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+          let ($($name,)+) = self;
+          $($name.write_to(writer);)+
+        }
+      }
+
+      impl<$($name: Readable),+> Readable for ($($name,)+) {
+        const SERIALIZED_SIZE: Option<usize> = {
+          let mut size = Some(0);
+          $(
+            size = match (size, $name::SERIALIZED_SIZE) {
+              (Some(a), Some(b)) => Some(a + b),
+              _ => None,
+            };
+          )+
+          size
+        };
+
+        fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+          Ok(($($name::read_from(reader)?,)+))
+        }
+      }
+
+      impl<$($name: SizeHint),+> SizeHint for ($($name,)+) {
+        #[allow(non_snake_case)]
+        fn serialized_size(&self) -> usize {
+          let ($($name,)+) = self;
+          0 $(+ $name.serialized_size())+
+        }
+      }
+    )*
+  };
+}
+
+// Tuples serialize as their elements in order with no framing, like a derived tuple struct.
+impl_tuple! {
+  (A)
+  (A, B)
+  (A, B, C)
+  (A, B, C, D)
+  (A, B, C, D, E)
+  (A, B, C, D, E, F)
+  (A, B, C, D, E, F, G)
+  (A, B, C, D, E, F, G, H)
+}
+
+macro_rules! impl_pointer {
+  ($($ptr:ident),*) => {
+    $(
+      impl<T: Writable + ?Sized> Writable for $ptr<T> {
+        fn write_to<S: Sink>(&self, writer: &mut BinaryWriter<S>) {
+          (**self).write_to(writer);
+        }
+      }
+
+      impl<T: Readable> Readable for $ptr<T> {
+        const SERIALIZED_SIZE: Option<usize> = T::SERIALIZED_SIZE;
+
+        fn read_from(reader: &mut BinaryReader<'_>) -> Result<Self, BinaryError> {
+          T::read_from(reader).map($ptr::new)
+        }
+
+        fn peek_size(reader: &BinaryReader<'_>, offset: usize) -> Option<usize> {
+          T::peek_size(reader, offset)
+        }
+      }
+
+      impl<T: SizeHint + ?Sized> SizeHint for $ptr<T> {
+        fn serialized_size(&self) -> usize {
+          (**self).serialized_size()
+        }
+      }
+    )*
+  };
+}
+
+// Smart pointers serialize transparently as their inner value. Shared ownership is not preserved:
+// two `Rc`s pointing at the same value are written twice and read back as independent allocations.
+impl_pointer!(Box, Rc, Arc);
+
+/// Formats a byte buffer as an `xxd`-style dump with offset, hex and ASCII columns, 16 bytes per line.
+/// Non-printable bytes are shown as `.` in the ASCII column. An empty buffer produces an empty string.
+pub fn hex_dump(data: &[u8]) -> String {
+  let mut out = String::new();
+  for (line, chunk) in data.chunks(16).enumerate() {
+    let mut hex = String::with_capacity(40);
+    for (i, byte) in chunk.iter().enumerate() {
+      if i > 0 && i % 2 == 0 {
+        hex.push(' ');
+      }
+      hex.push_str(&format!("{:02x}", byte));
+    }
+    let ascii: String = chunk
+      .iter()
+      .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+      .collect();
+    out.push_str(&format!("{:08x}: {:<39}  {}\n", line * 16, hex, ascii));
+  }
+  out
+}
+
+/// A decoder step exercised by `try_decode_all`.
+#[cfg(feature = "fuzzing")]
+type DecodeStep = fn(&mut BinaryReader<'_>) -> Result<(), BinaryError>;
+
+/// Every reader method `try_decode_all` runs, each discarding the decoded value.
+#[cfg(feature = "fuzzing")]
+const DECODE_STEPS: &[DecodeStep] = &[
+  |r| r.read_u8().map(drop),
+  |r| r.read_u16().map(drop),
+  |r| r.read_u32().map(drop),
+  |r| r.read_u64().map(drop),
+  |r| r.read_u24().map(drop),
+  |r| r.read_i24().map(drop),
+  |r| r.read_i8().map(drop),
+  |r| r.read_i16().map(drop),
+  |r| r.read_i32().map(drop),
+  |r| r.read_i64().map(drop),
+  |r| r.read_varint().map(drop),
+  |r| r.read_usize().map(drop),
+  |r| r.read_isize().map(drop),
+  |r| r.read_f32().map(drop),
+  |r| r.read_f64().map(drop),
+  |r| r.read_f32_finite().map(drop),
+  |r| r.read_f64_finite().map(drop),
+  |r| r.read_bool().map(drop),
+  |r| r.read_char().map(drop),
+  |r| r.read_string().map(drop),
+  |r| r.read_str().map(drop),
+  |r| r.read_string_lossy().map(drop),
+  |r| r.read_string_with_prefix(PrefixWidth::U8).map(drop),
+  |r| r.read_string_with_prefix(PrefixWidth::U64).map(drop),
+  |r| r.read_bytes_u8len().map(drop),
+  |r| r.read_frame().map(drop),
+  |r| r.read_message().map(drop),
+  |r| r.read_version().map(drop),
+  |r| r.read_vec_u8().map(drop),
+  |r| r.read_vec_u8_ref().map(drop),
+  |r| r.read_vec_u16().map(drop),
+  |r| r.read_vec_u32().map(drop),
+  |r| r.read_vec_u64().map(drop),
+  |r| r.read_vec_u24().map(drop),
+  |r| r.read_vec_i24().map(drop),
+  |r| r.read_vec_i8().map(drop),
+  |r| r.read_vec_i16().map(drop),
+  |r| r.read_vec_i32().map(drop),
+  |r| r.read_vec_i64().map(drop),
+  |r| r.read_vec_f32().map(drop),
+  |r| r.read_vec_f64().map(drop),
+  |r| r.read_pairs_u32_f64().map(drop),
+  |r| r.read_vec_char().map(drop),
+  |r| r.read_vec_bool_rle_bounded(1 << 16).map(drop),
+  |r| r.read_vec_string().map(drop),
+  |r| r.read_vec_bytes().map(drop),
+  |r| r.read_vec_string_packed().map(drop),
+  |r| r.read_vec_u32_bounded(16).map(drop),
+  |r| r.read_vec_with_prefix::<u16>(PrefixWidth::Varint).map(drop),
+  |r| r.read_string_with_prefix(PrefixWidth::Varint).map(drop),
+  |r| r.read_vec::<(u8, String)>().map(drop),
+  |r| r.read::<HashMap<u16, Vec<u8>>>().map(drop),
+  |r| r.read_set::<u32>(true).map(drop),
+  |r| r.read_result::<u64, String>().map(drop),
+  |r| r.read_range_inclusive::<i32>().map(drop),
+  |r| r.read_vec::<()>().map(drop),
+  |r| r.read_vec_with_prefix::<PhantomData<u64>>(PrefixWidth::Varint).map(drop),
+  |r| {
+    r.read_remaining();
+    Ok(())
+  },
+];
+
+/// Runs every reader method over `data`, for fuzzing. Each method is tried on a fresh reader in both
+/// byte orders, then all of them are chained on one reader until the first error. Malformed input
+/// must come back as an `Err`; any panic (or a cursor past the end) is a bug the fuzzer reports.
+#[cfg(feature = "fuzzing")]
+pub fn try_decode_all(data: &[u8]) {
+  for endianness in [Endianness::Little, Endianness::Big] {
+    for step in DECODE_STEPS {
+      let mut reader = BinaryReader::new_with_endianness(data, endianness);
+      let _ = step(&mut reader);
+      assert!(reader.remaining() <= data.len());
+    }
+    let mut reader = BinaryReader::new_with_endianness(data, endianness);
+    for step in DECODE_STEPS.iter().cycle().take(DECODE_STEPS.len() * 4) {
+      if step(&mut reader).is_err() {
+        break;
+      }
+    }
+  }
+}
+
+/// The default cap on how many elements a vector reader reserves before decoding them.
+const DEFAULT_MAX_PREALLOC: usize = 4096;
+
+/// The longest run-length encoded bool vector `read_vec_bool_rle` accepts (16 Mi values).
+pub const DEFAULT_MAX_BOOL_RLE_LEN: usize = 1 << 24;
+
+/// The longest vector or set of zero-sized elements (`()`, `PhantomData`) that `read_vec`,
+/// `read_vec_with_prefix`, `read_set`, `read_hash_set` and the `Readable` set impls accept, and the
+/// most entries `read_map_into` and the `Readable` map impls accept when key and value are both
+/// zero-sized. Such elements take no bytes, so the remaining input cannot bound
+/// their count, and a bogus length would otherwise spin through billions of empty reads.
+pub const MAX_ZERO_SIZED_LEN: usize = 1 << 16;
+
+/// Errors with `LengthOverflow` if `T` is zero-sized and `length` exceeds `MAX_ZERO_SIZED_LEN`.
+fn check_zero_sized_len<T: Readable>(length: usize) -> Result<(), BinaryError> {
+  if T::SERIALIZED_SIZE == Some(0) && length > MAX_ZERO_SIZED_LEN {
+    return Err(BinaryError::LengthOverflow { length, max: MAX_ZERO_SIZED_LEN as u64 });
+  }
+  Ok(())
+}
+
+/// Returns how many slots to reserve for a vector whose length prefix claims `length` elements.
+fn capacity_hint(length: usize, max_prealloc: usize) -> usize {
+  length.min(max_prealloc)
+}
+
+/// Errors unless `value` fits in 24 unsigned bits.
+fn check_u24(value: u32) -> Result<(), BinaryError> {
+  if value > 0xFF_FFFF {
+    return Err(BinaryError::InvalidValue(format!("{} does not fit in 24 bits", value)));
+  }
+  Ok(())
+}
+
+/// Errors unless `value` fits in 24 signed bits.
+fn check_i24(value: i32) -> Result<(), BinaryError> {
+  if !(-0x80_0000..=0x7F_FFFF).contains(&value) {
+    return Err(BinaryError::InvalidValue(format!("{} does not fit in 24 bits", value)));
+  }
+  Ok(())
+}
+
+/// The largest fixed-point scale accepted by `write_fixed_point` and `read_fixed_point`.
+const MAX_FIXED_POINT_SCALE: u8 = 38;
+
+fn check_scale(scale: u8) -> Result<(), BinaryError> {
+  if scale > MAX_FIXED_POINT_SCALE {
+    return Err(BinaryError::InvalidValue(format!("fixed-point scale {} exceeds {}", scale, MAX_FIXED_POINT_SCALE)));
+  }
+  Ok(())
+}
+
+/// Converts a decoded u64 into a usize, erroring if it exceeds `limit` (the target's `usize::MAX`).
+fn fit_usize(value: u64, limit: u64) -> Result<usize, BinaryError> {
+  if value > limit {
+    Err(BinaryError::UsizeOverflow(value))
+  } else {
+    Ok(value as usize)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::consts::PI;
+  use std::f64::consts::E;
+
+  #[test]
+  fn test_binary_writer_reader() {
+    let mut writer = BinaryWriter::new();
+
+    // Write various data types
+    writer.write_u8(255);
+    writer.write_i8(-128);
+    writer.write_u16(65535);
+    writer.write_i16(-32768);
+    writer.write_u32(4294967295);
+    writer.write_i32(-2147483648);
+    writer.write_u64(18446744073709551615);
+    writer.write_i64(-9223372036854775808);
+    writer.write_f32(PI);
+    writer.write_f64(E);
+    writer.write_bool(true);
+    writer.write_string("Hello, World!");
+
+    let data = writer.get_data().clone();
+
+    let mut reader = BinaryReader::new(&data);
+
+    // Read and assert the values
+    assert_eq!(reader.read_u8().unwrap(), 255);
+    assert_eq!(reader.read_i8().unwrap(), -128);
+    assert_eq!(reader.read_u16().unwrap(), 65535);
+    assert_eq!(reader.read_i16().unwrap(), -32768);
+    assert_eq!(reader.read_u32().unwrap(), 4294967295);
+    assert_eq!(reader.read_i32().unwrap(), -2147483648);
+    assert_eq!(reader.read_u64().unwrap(), 18446744073709551615);
+    assert_eq!(reader.read_i64().unwrap(), -9223372036854775808);
+    assert!((reader.read_f32().unwrap() - PI).abs() < 1e-6);
+    assert!((reader.read_f64().unwrap() - E).abs() < 1e-12);
+    assert!(reader.read_bool().unwrap());
+    assert_eq!(reader.read_string().unwrap(), "Hello, World!");
+  }
+
+  #[test]
+  fn test_binary_writer_reader_vectors() {
+    let mut writer = BinaryWriter::new();
+
+    // Write vectors
+    writer.write_vec_u8(&[1, 2, 3, 4, 5]);
+    writer.write_vec_i16(&[-1, -2, -3]);
+    writer.write_vec_f64(&[1.1, 2.2, 3.3]);
+
+    let data = writer.get_data().clone();
+
+    let mut reader = BinaryReader::new(&data);
+
+    // Read and assert the vectors
+    assert_eq!(reader.read_vec_u8().unwrap(), vec![1, 2, 3, 4, 5]);
+    assert_eq!(reader.read_vec_i16().unwrap(), vec![-1, -2, -3]);
+    let read_f64 = reader.read_vec_f64().unwrap();
+    assert_eq!(read_f64.len(), 3);
+    assert!((read_f64[0] - 1.1).abs() < 1e-10);
+    assert!((read_f64[1] - 2.2).abs() < 1e-10);
+    assert!((read_f64[2] - 3.3).abs() < 1e-10);
+  }
+
+  #[test]
+  fn test_binary_writer_reader_vec_string() {
+    let mut writer = BinaryWriter::new();
+
+    // Write a vector of strings
+    let strings = vec![
+      "Hello".to_string(),
+      "Bin-It".to_string(),
+      "Serialization".to_string(),
+      "".to_string(),
+      "🚀✨".to_string(),
+    ];
+    writer.write_vec_string(&strings);
+
+    let data = writer.get_data().clone();
+
+    let mut reader = BinaryReader::new(&data);
+
+    // Read and assert the vector of strings
+    let read_strings = reader.read_vec_string().unwrap();
+    assert_eq!(read_strings, strings);
+  }
+
+  #[test]
+  fn test_binary_reader_error() {
+    let data = vec![1, 2]; // Insufficient data for a u32
+
+    let mut reader = BinaryReader::new(&data);
+
+    // Attempt to read a u32, which should fail
+    assert!(reader.read_u32().is_err());
+  }
+
+  #[test]
+  fn test_binary_writer_reader_vec_char() {
+    let mut writer = BinaryWriter::new();
+
+    // Write a vector of chars, including multi-byte code points
+    let chars = vec!['a', 'é', '中', '🚀'];
+    writer.write_vec_char(&chars);
+
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+
+    // Read and assert the vector of chars
+    assert_eq!(reader.read_vec_char().unwrap(), chars);
+  }
+
+  #[test]
+  fn test_binary_reader_invalid_char() {
+    let mut writer = BinaryWriter::new();
+
+    // Write a count of 2 followed by a valid char and a surrogate code point
+    writer.write_u32(2);
+    writer.write_char('a');
+    writer.write_u32(0xD800);
+
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+
+    // The surrogate must be rejected rather than replaced
+    assert_eq!(reader.read_vec_char(), Err(BinaryError::InvalidChar { value: 0xD800, offset: 8 }));
+  }
+
+  #[test]
+  fn test_binary_writer_reader_usize_isize() {
+    let mut writer = BinaryWriter::new();
+
+    // Write pointer-width integers, which always take 8 bytes on the wire
+    writer.write_usize(123456);
+    writer.write_isize(-42);
+
+    let data = writer.get_data();
+    assert_eq!(data.len(), 16);
+
+    let mut reader = BinaryReader::new(&data);
+
+    // Read and assert the values
+    assert_eq!(reader.read_usize().unwrap(), 123456);
+    assert_eq!(reader.read_isize().unwrap(), -42);
+  }
+
+  #[test]
+  fn test_fit_usize_32_bit_limit() {
+    let limit = u32::MAX as u64;
+
+    // A value that fits in a 32-bit usize is accepted
+    assert_eq!(fit_usize(4_000_000_000, limit).unwrap(), 4_000_000_000);
+
+    // A value beyond a 32-bit usize is rejected
+    assert_eq!(fit_usize(1 << 32, limit), Err(BinaryError::UsizeOverflow(1 << 32)));
+  }
+
+  #[test]
+  fn test_binary_reader_large_vec_u32() {
+    let mut writer = BinaryWriter::new();
+
+    // A few hundred elements take the same bulk path as a huge vector
+    let values: Vec<u32> = (0..300).map(|i: u32| i.wrapping_mul(0x0101_0101)).collect();
+    writer.write_vec_u32(&values);
+
+    let data = writer.get_data();
+    assert_eq!(data.len(), 4 + 300 * 4);
+
+    let mut reader = BinaryReader::new(&data);
+
+    // Read and assert the vector
+    assert_eq!(reader.read_vec_u32().unwrap(), values);
+  }
+
+  #[test]
+  fn test_binary_reader_truncated_vec() {
+    let mut writer = BinaryWriter::new();
+
+    // Claim three u64 values but only provide two
+    writer.write_u32(3);
+    writer.write_u64(1);
+    writer.write_u64(2);
+
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+
+    // The whole payload is checked up front
+    assert_eq!(reader.read_vec_u64(), Err(BinaryError::UnexpectedEof { needed: 24, available: 16, offset: 4 }));
+  }
+
+  #[test]
+  fn test_hex_dump() {
+    // Short buffers show their bytes and ASCII on a single line
+    let dump = hex_dump(&[0x48, 0x69]);
+    assert_eq!(dump, format!("00000000: {:<39}  Hi\n", "4869"));
+
+    // Buffers longer than 16 bytes wrap onto a new line with the next offset
+    let data: Vec<u8> = (0..20).collect();
+    let dump = hex_dump(&data);
+    let lines: Vec<&str> = dump.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("00000000: 0001 0203 0405 0607 0809 0a0b 0c0d 0e0f  "));
+    assert!(lines[1].starts_with("00000010: 1011 1213"));
+
+    // Empty input produces no output
+    assert_eq!(hex_dump(&[]), "");
+  }
+
+  #[test]
+  fn test_binary_writer_vec_u32_layout() {
+    let mut writer = BinaryWriter::new();
+
+    // Write a vector of u32 through the bulk path
+    writer.write_vec_u32(&[1, 0x01020304]);
+
+    let data = writer.get_data();
+
+    // The bytes must match the per-element little-endian layout
+    assert_eq!(data, vec![2, 0, 0, 0, 1, 0, 0, 0, 4, 3, 2, 1]);
+  }
+
+  #[test]
+  fn test_binary_writer_incremental_hash() {
+    use std::collections::hash_map::DefaultHasher;
+
+    let mut writer = BinaryWriter::with_hasher(DefaultHasher::new());
+
+    // Write three values, each updating the hash incrementally
+    writer.write_u32(42);
+    writer.write_string("Hello, Bin-It!");
+    writer.write_vec_u32(&[1, 2, 3]);
+
+    let (data, hash) = writer.get_data_with_hash();
+
+    // DefaultHasher does not depend on how its input is split, so one shot over the buffer matches
+    let mut one_shot = DefaultHasher::new();
+    one_shot.write(&data);
+    assert_eq!(hash, Some(one_shot.finish()));
+
+    // Writers without a hasher report no hash
+    assert_eq!(BinaryWriter::new().hash(), None);
+  }
+
+  #[test]
+  fn test_prelude_imports() {
+    use crate::prelude::*;
+
+    let mut writer = BinaryWriter::new();
+    writer.write_u8(7);
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_u8().unwrap(), 7);
+    assert_eq!(reader.read_u8(), Err(BinaryError::UnexpectedEof { needed: 1, available: 0, offset: 1 }));
+  }
+
+  #[test]
+  fn test_binary_reader_capacity_hint() {
+    let mut writer = BinaryWriter::new();
+
+    // Claim a million strings but only provide a few bytes of payload
+    writer.write_u32(1_000_000);
+    writer.write_string("a");
+
+    let data = writer.get_data();
+
+    // Pre-allocation is capped rather than trusting the prefix
+    assert_eq!(capacity_hint(1_000_000, DEFAULT_MAX_PREALLOC), DEFAULT_MAX_PREALLOC);
+    assert_eq!(capacity_hint(3, DEFAULT_MAX_PREALLOC), 3);
+
+    let mut reader = BinaryReader::new(&data);
+    let strings = reader.read_vec_with_capacity_hint(DEFAULT_MAX_PREALLOC, BinaryReader::read_string);
+    assert_eq!(strings, Err(BinaryError::UnexpectedEof { needed: 4, available: 0, offset: 9 }));
+
+    // A custom cap still decodes every element when the data is present
+    let mut writer = BinaryWriter::new();
+    writer.write_vec_string(&["x".to_string(), "y".to_string(), "z".to_string()]);
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+    let strings = reader.read_vec_with_capacity_hint(1, BinaryReader::read_string).unwrap();
+    assert_eq!(strings, vec!["x", "y", "z"]);
+  }
+
+  #[test]
+  fn test_binary_writer_reader_nested_vec() {
+    let mut writer = BinaryWriter::new();
+
+    // Write jagged two-level structures through the generic trait path
+    let numbers: Vec<Vec<u32>> = vec![vec![1, 2, 3], vec![], vec![4]];
+    let words: Vec<Vec<String>> = vec![vec!["a".to_string(), "bc".to_string()], vec!["def".to_string()]];
+    writer.write_vec(&numbers);
+    writer.write(&words);
+
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+
+    // Read and assert the nested vectors
+    assert_eq!(reader.read_vec::<Vec<u32>>().unwrap(), numbers);
+    assert_eq!(reader.read::<Vec<Vec<String>>>().unwrap(), words);
+  }
+
+  #[test]
+  fn test_generic_vec_matches_typed_vec() {
+    let mut generic = BinaryWriter::new();
+    let mut typed = BinaryWriter::new();
+
+    // The generic path must produce the same bytes as the typed helpers
+    generic.write_vec(&[1u16, 2, 3]);
+    typed.write_vec_u16(&[1, 2, 3]);
+
+    assert_eq!(generic.get_data(), typed.get_data());
+  }
+
+  #[test]
+  fn test_binary_writer_reader_big_endian() {
+    let mut writer = BinaryWriter::new();
+
+    // Write a little-endian u32 and big-endian values of every width
+    writer.write_u32(0x01020304);
+    writer.write_u16_be(0xBEEF);
+    writer.write_i64_be(-2);
+    writer.write_f64_be(1.5);
+
+    let data = writer.get_data();
+    assert_eq!(&data[4..6], &[0xBE, 0xEF]);
+
+    let mut reader = BinaryReader::new(&data);
+
+    // Reading the little-endian u32 as big-endian sees the bytes swapped
+    assert_eq!(reader.read_u32_be().unwrap(), 0x04030201);
+    assert_eq!(reader.read_u16_be().unwrap(), 0xBEEF);
+    assert_eq!(reader.read_i64_be().unwrap(), -2);
+    assert_eq!(reader.read_f64_be().unwrap(), 1.5);
+  }
+
+  #[test]
+  fn test_binary_writer_finish() {
+    let mut writer = BinaryWriter::new();
+    writer.write_u16(0x0102);
+
+    // The in-memory writer finishes with the same bytes as get_data
+    assert_eq!(writer.finish().unwrap(), vec![0x02, 0x01]);
+  }
+
+  #[test]
+  fn test_binary_writer_dyn_write_sink() {
+    // Write the same values through the Vec path and through a `dyn Write` sink
+    let mut vec_writer = BinaryWriter::new();
+    vec_writer.write_u32(42);
+    vec_writer.write_string("Hello, Bin-It!");
+    vec_writer.write_vec_f64(&[1.1, 2.2]);
+    let expected = vec_writer.get_data();
+
+    let mut out: Vec<u8> = Vec::new();
+    let sink: &mut dyn io::Write = &mut out;
+    let mut writer = BinaryWriter::from_sink(sink);
+    writer.write_u32(42);
+    writer.write_string("Hello, Bin-It!");
+    writer.write_vec_f64(&[1.1, 2.2]);
+    writer.finish().unwrap();
+
+    assert_eq!(out, expected);
+  }
+
+  #[test]
+  fn test_binary_writer_sink_error() {
+    struct FailingWrite;
+
+    impl io::Write for FailingWrite {
+      fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+      }
+
+      fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+      }
+    }
+
+    let mut failing = FailingWrite;
+    let sink: &mut dyn io::Write = &mut failing;
+    let mut writer = BinaryWriter::from_sink(sink);
+    writer.write_u32(1);
+    writer.write_u32(2);
+
+    // The sink's failure surfaces when the writer is finished
+    assert_eq!(writer.finish().err(), Some(BinaryError::Io(io::ErrorKind::BrokenPipe)));
+  }
+
+  #[test]
+  fn test_binary_writer_reader_debug() {
+    let mut writer = BinaryWriter::new();
+    writer.write_u8(0x48);
+    writer.write_u8(0x69);
+    writer.write_u16(0x0102);
+
+    // The writer prints its length and a hex dump
+    assert_eq!(writer.to_hex_string(), "48690201");
+    let debug = format!("{:?}", writer);
+    assert!(debug.starts_with("BinaryWriter (4 bytes)\n00000000: 4869 0201"));
+    assert!(debug.contains("Hi.."));
+
+    let data = writer.get_data();
+    let mut reader = BinaryReader::new(&data);
+    reader.read_u8().unwrap();
+    reader.read_u8().unwrap();
+
+    // The reader marks the next byte to be read
+    let debug = format!("{:?}", reader);
+    let lines: Vec<&str> = debug.lines().collect();
+    assert_eq!(lines[0], "BinaryReader (cursor 2 of 4 bytes)");
+    assert_eq!(&lines[1][15..17], "02");
+    assert_eq!(lines[2], format!("{:15}^^", ""));
+  }
+
+  #[test]
+  fn test_binary_reader_read_into() {
+    let data = vec![1, 2, 3, 4, 5, 6];
+    let mut reader = BinaryReader::new(&data);
+
+    // Fill a reusable buffer twice
+    let mut buf = [0u8; 4];
+    reader.read_into(&mut buf).unwrap();
+    assert_eq!(buf, [1, 2, 3, 4]);
+
+    // Only two bytes remain, so a four-byte read fails and leaves the buffer untouched
+    assert_eq!(reader.read_into(&mut buf), Err(BinaryError::UnexpectedEof { needed: 4, available: 2, offset: 4 }));
+    assert_eq!(buf, [1, 2, 3, 4]);
+
+    reader.read_into(&mut buf[..2]).unwrap();
+    assert_eq!(&buf[..2], &[5, 6]);
+  }
+
+  #[test]
+  fn test_binary_reader_skip() {
+    let mut writer = BinaryWriter::new();
+
+    // Write fields to skip, each followed by one to read
+    writer.write_string("skip me");
+    writer.write_u32(7);
+    writer.write_vec_u64(&[1, 2, 3]);
+    writer.write_u32(8);
+    writer.write_string("truncated");
+
+    let mut data = writer.get_data();
+    data.truncate(data.len() - 2);
+
+    let mut reader = BinaryReader::new(&data);
+
+    // Skipping advances past the payload without decoding it
+    reader.skip_string().unwrap();
+    assert_eq!(reader.read_u32().unwrap(), 7);
+    reader.skip_vec::<u64>().unwrap();
+    assert_eq!(reader.read_u32().unwrap(), 8);
+
+    // Skipping is still bounds-checked
+    assert_eq!(reader.skip_string(), Err(BinaryError::UnexpectedEof { needed: 9, available: 7, offset: 51 }));
+  }
+
+  #[test]
+  fn test_binary_writer_reader_float_bits() {
+    // Signaling NaNs: quiet bit clear, non-zero payload
+    let snan_f32: u32 = 0x7f80_0001;
+    let snan_f64: u64 = 0x7ff0_0000_0000_0001;
+
+    let mut writer = BinaryWriter::new();
+    writer.write_f32_bits(snan_f32);
+    writer.write_f64_bits(snan_f64);
+    writer.write_f32_bits(1.5f32.to_bits());
+
+    let data = writer.get_data();
+    let mut reader = BinaryReader::new(&data);
+
+    // The bit patterns survive unchanged
+    assert_eq!(reader.read_f32_bits().unwrap(), snan_f32);
+    assert_eq!(reader.read_f64_bits().unwrap(), snan_f64);
+    assert_eq!(reader.read_f32().unwrap(), 1.5);
+  }
+
+  #[test]
+  fn test_binary_writer_reader_string_with_prefix() {
+    let mut writer = BinaryWriter::new();
+
+    // Write the same string with each prefix width
+    for prefix in [PrefixWidth::U8, PrefixWidth::U16, PrefixWidth::U32, PrefixWidth::U64] {
+      writer.write_string_with_prefix("Bin-It", prefix).unwrap();
+    }
+
+    // A string longer than 255 bytes does not fit a u8 prefix
+    let long = "x".repeat(256);
+    assert_eq!(
+      writer.write_string_with_prefix(&long, PrefixWidth::U8).err(),
+      Some(BinaryError::LengthOverflow { length: 256, max: 255 })
+    );
+
+    let data = writer.get_data();
+    assert_eq!(data.len(), (1 + 2 + 4 + 8) + 4 * 6);
+
+    let mut reader = BinaryReader::new(&data);
+
+    // Read and assert each string
+    for prefix in [PrefixWidth::U8, PrefixWidth::U16, PrefixWidth::U32, PrefixWidth::U64] {
+      assert_eq!(reader.read_string_with_prefix(prefix).unwrap(), "Bin-It");
+    }
+  }
+
+  #[test]
+  fn test_binary_writer_chaining() {
+    // Chained writes
+    let mut chained = BinaryWriter::new();
+    chained.write_u32(1).write_string("x").write_bool(true).write_vec_u16(&[2, 3]);
+
+    // Sequential writes
+    let mut sequential = BinaryWriter::new();
+    sequential.write_u32(1);
+    sequential.write_string("x");
+    sequential.write_bool(true);
+    sequential.write_vec_u16(&[2, 3]);
+
+    assert_eq!(chained.get_data(), sequential.get_data());
+
+    // Fallible writes chain through `?` or `unwrap`, and configuration calls chain too
+    let mut writer = BinaryWriter::new();
+    writer
+      .set_endianness(Endianness::Big)
+      .write_u16(1)
+      .write_string_with_prefix("y", PrefixWidth::U8)
+      .unwrap()
+      .write_bytes_u8len(&[7])
+      .unwrap()
+      .write_padding(1, 0);
+    assert_eq!(writer.get_data(), [0, 1, 1, b'y', 1, 7, 0]);
+  }
+
+  #[test]
+  fn test_binary_writer_max_size() {
+    let mut writer = BinaryWriter::with_max_size(10);
+
+    // Writes within the budget succeed
+    writer.try_write(&1u32).unwrap();
+    writer.try_write(&2u32).unwrap();
+
+    // A write that would grow the buffer past 10 bytes fails
+    assert_eq!(writer.try_write(&3u32).err(), Some(BinaryError::SizeLimitExceeded { limit: 10 }));
+
+    // The failure sticks and is reported again on finish
+    writer.write_u8(4);
+    assert_eq!(writer.finish().err(), Some(BinaryError::SizeLimitExceeded { limit: 10 }));
+
+    // Exactly filling the budget is allowed
+    let mut writer = BinaryWriter::with_max_size(4);
+    writer.write_u32(1);
+    assert_eq!(writer.finish().unwrap(), vec![1, 0, 0, 0]);
+  }
+
+  #[test]
+  fn test_binary_reader_read_u32_into() {
+    let mut writer = BinaryWriter::new();
+
+    // Write eight u32 values with no length prefix
+    for v in 0..8u32 {
+      writer.write_u32(v);
+    }
+
+    let data = writer.get_data();
+    let mut reader = BinaryReader::new(&data);
+
+    // Fill a four-slot buffer twice, then find the buffer drained
+    let mut out = [0u32; 4];
+    assert_eq!(reader.read_u32_into(&mut out).unwrap(), 4);
+    assert_eq!(out, [0, 1, 2, 3]);
+    assert_eq!(reader.read_u32_into(&mut out).unwrap(), 4);
+    assert_eq!(out, [4, 5, 6, 7]);
+    assert_eq!(reader.read_u32_into(&mut out).unwrap(), 0);
+
+    // The exact variant fills the whole slice or errors
+    let mut reader = BinaryReader::new(&data);
+    let mut exact = [0u32; 6];
+    reader.read_exact_into(&mut exact).unwrap();
+    assert_eq!(exact, [0, 1, 2, 3, 4, 5]);
+    assert_eq!(reader.read_exact_into(&mut exact), Err(BinaryError::UnexpectedEof { needed: 4, available: 0, offset: 32 }));
+  }
+
+  #[test]
+  fn test_binary_writer_reader_pairs() {
+    let mut writer = BinaryWriter::new();
+
+    // Write parallel key/value columns under one length prefix
+    writer.write_pairs_u32_f64(&[1, 2, 3], &[0.5, 1.5, 2.5]).unwrap();
+
+    // Mismatched columns are rejected
+    assert_eq!(
+      writer.write_pairs_u32_f64(&[1, 2], &[0.5]).err(),
+      Some(BinaryError::LengthMismatch { left: 2, right: 1 })
+    );
+
+    let data = writer.get_data();
+    assert_eq!(data.len(), 4 + 3 * 4 + 3 * 8);
+
+    let mut reader = BinaryReader::new(&data);
+    let (keys, vals) = reader.read_pairs_u32_f64().unwrap();
+    assert_eq!(keys, vec![1, 2, 3]);
+    assert_eq!(vals, vec![0.5, 1.5, 2.5]);
+  }
+
+  #[test]
+  fn test_binary_writer_reader_unit() {
+    let mut writer = BinaryWriter::new();
+
+    // The unit type occupies no bytes
+    writer.write(&());
+    writer.write_u8(1);
+
+    // A vector of units is encoded by its count alone
+    writer.write_vec(&[(), (), ()]);
+
+    let data = writer.get_data();
+    assert_eq!(data, vec![1, 3, 0, 0, 0]);
+
+    let mut reader = BinaryReader::new(&data);
+    reader.read::<()>().unwrap();
+    assert_eq!(reader.read_u8().unwrap(), 1);
+    assert_eq!(reader.read_vec::<()>().unwrap().len(), 3);
+
+    // A bogus count of zero-sized elements is rejected instead of looped over
+    let data = u32::MAX.to_le_bytes();
+    let err = BinaryReader::new(&data).read_vec::<()>().unwrap_err();
+    assert_eq!(err, BinaryError::LengthOverflow { length: u32::MAX as usize, max: MAX_ZERO_SIZED_LEN as u64 });
+    let mut writer = BinaryWriter::new();
+    writer.write_varint(MAX_ZERO_SIZED_LEN as u64 + 1);
+    let data = writer.get_data();
+    let result = BinaryReader::new(&data).read_vec_with_prefix::<PhantomData<u64>>(PrefixWidth::Varint);
+    assert!(matches!(result, Err(BinaryError::LengthOverflow { .. })));
+  }
+
+  #[test]
+  fn test_binary_reader_read_remaining() {
+    let mut writer = BinaryWriter::new();
+
+    // Write a header followed by an unprefixed trailer
+    writer.write_u16(7);
+    writer.write_u8(b'a').write_u8(b'b').write_u8(b'c');
+
+    let data = writer.get_data();
+    let mut reader = BinaryReader::new(&data);
+
+    assert_eq!(reader.read_u16().unwrap(), 7);
+    assert_eq!(reader.remaining(), 3);
+    assert_eq!(reader.read_remaining(), b"abc");
+
+    // The reader is now at the end
+    assert_eq!(reader.remaining(), 0);
+    assert_eq!(reader.read_remaining_vec(), Vec::<u8>::new());
+  }
+
+  #[test]
+  fn test_binary_writer_reader_smart_pointers() {
+    let mut writer = BinaryWriter::new();
+
+    // Pointers write exactly the bytes of their inner value
+    let shared = Rc::new(5u32);
+    writer.write(&Box::new(1u16));
+    writer.write(&shared);
+    writer.write(&shared);
+    writer.write(&Arc::new("arc".to_string()));
+
+    let data = writer.get_data();
+    assert_eq!(data.len(), 2 + 4 + 4 + 4 + 3);
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(*reader.read::<Box<u16>>().unwrap(), 1);
+
+    // Each Rc is decoded independently, so identity is not preserved
+    let a = reader.read::<Rc<u32>>().unwrap();
+    let b = reader.read::<Rc<u32>>().unwrap();
+    assert_eq!(a, b);
+    assert!(!Rc::ptr_eq(&a, &b));
+
+    assert_eq!(*reader.read::<Arc<String>>().unwrap(), "arc");
+  }
+
+  #[test]
+  fn test_binary_reader_huge_length_prefix() {
+    let mut writer = BinaryWriter::new();
+
+    // A u64 length prefix close to usize::MAX, after some earlier data
+    writer.write_u64(0);
+    writer.write_u64(u64::MAX - 2);
+    writer.write_u8(b'x');
+
+    let data = writer.get_data();
+    let mut reader = BinaryReader::new(&data);
+    reader.read_u64().unwrap();
+
+    // The bounds check must not wrap around and pass
+    assert_eq!(
+      reader.read_string_with_prefix(PrefixWidth::U64),
+      Err(BinaryError::UnexpectedEof { needed: (u64::MAX - 2) as usize, available: 1, offset: 16 })
+    );
+  }
+
+  #[test]
+  fn test_binary_writer_reader_packed_strings() {
+    let mut writer = BinaryWriter::new();
+
+    // Write 1000 short strings in packed form
+    let strings: Vec<String> = (0..1000).map(|i| format!("s{}", i)).collect();
+    writer.write_vec_string_packed(&strings);
+
+    let data = writer.get_data();
+    let mut reader = BinaryReader::new(&data);
+    let packed = reader.read_packed_strings().unwrap();
+    assert_eq!(packed.len(), 1000);
+
+    // Random access by index
+    let mut index = 7usize;
+    for _ in 0..100 {
+      index = (index * 31 + 17) % 1000;
+      assert_eq!(packed.get(index).unwrap(), strings[index]);
+    }
+    assert_eq!(packed.get(1000), None);
+
+    // The owned form reconstructs the whole vector
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_vec_string_packed().unwrap(), strings);
+  }
+
+  #[test]
+  fn test_binary_reader_packed_strings_bad_offsets() {
+    let mut writer = BinaryWriter::new();
+
+    // Two strings whose offsets go backwards
+    writer.write_u32(2).write_u32(0).write_u32(3).write_u32(1);
+    writer.write_u8(b'a').write_u8(b'b').write_u8(b'c');
+
+    let data = writer.get_data();
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_vec_string_packed(), Err(BinaryError::InvalidOffset { index: 1 }));
+  }
+
+  #[test]
+  fn test_binary_writer_reader_runtime_endianness() {
+    let mut writer = BinaryWriter::new_with_endianness(Endianness::Big);
+    assert_eq!(writer.endianness(), Endianness::Big);
+
+    // Every multi-byte value, including length prefixes, follows the selected order
+    writer.write_u32(0x01020304);
+    writer.write_vec_u16(&[0x0506]);
+    writer.write_f64(1.5);
+    writer.write_u16_le(0x0708);
+
+    let data = writer.get_data();
+    assert_eq!(&data[..10], &[1, 2, 3, 4, 0, 0, 0, 1, 5, 6]);
+
+    let mut reader = BinaryReader::new_with_endianness(&data, Endianness::Big);
+    assert_eq!(reader.read_u32().unwrap(), 0x01020304);
+    assert_eq!(reader.read_vec_u16().unwrap(), vec![0x0506]);
+    assert_eq!(reader.read_f64().unwrap(), 1.5);
+    assert_eq!(reader.read_u16_le().unwrap(), 0x0708);
+
+    // The default remains little-endian
+    assert_eq!(BinaryReader::new(&data).endianness(), Endianness::Little);
+  }
+
+  #[test]
+  fn test_binary_reader_peek_length() {
+    let mut writer = BinaryWriter::new();
+    writer.write_string("Hello, Bin-It!");
+
+    let data = writer.get_data();
+    let mut reader = BinaryReader::new(&data);
+
+    // Peeking does not move the cursor, so the read sees the same count
+    assert_eq!(reader.peek_length().unwrap(), 14);
+    assert_eq!(reader.peek_length().unwrap(), 14);
+    assert_eq!(reader.read_string().unwrap().len(), 14);
+
+    // There is no prefix left to peek
+    assert_eq!(reader.peek_length(), Err(BinaryError::UnexpectedEof { needed: 4, available: 0, offset: 18 }));
+  }
+
+  #[cfg(feature = "derive")]
+  #[test]
+  fn test_derive_struct_and_enum() {
+    #[derive(BinWrite, BinRead, Debug, PartialEq)]
+    struct Point {
+      x: i32,
+      y: i32,
+      label: String,
+    }
+
+    #[derive(BinWrite, BinRead, Debug, PartialEq)]
+    enum Shape {
+      Empty,
+      Circle(Point, u32),
+      Polygon { points: Vec<Point> },
+    }
+
+    let origin = Point { x: 0, y: -1, label: "o".to_string() };
+    let shapes = vec![
+      Shape::Empty,
+      Shape::Circle(Point { x: 1, y: 2, label: "c".to_string() }, 5),
+      Shape::Polygon { points: vec![Point { x: 3, y: 4, label: "p".to_string() }] },
+    ];
+
+    let mut writer = BinaryWriter::new();
+    writer.write(&origin);
+    writer.write_vec(&shapes);
+
+    let data = writer.get_data();
+    // Fields are written in order with no framing
+    assert_eq!(&data[..13], &[0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 1, 0, 0, 0, b'o']);
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read::<Point>().unwrap(), origin);
+    assert_eq!(reader.read_vec::<Shape>().unwrap(), shapes);
+
+    // An unknown tag is rejected
+    let mut reader = BinaryReader::new(&[9, 0, 0, 0]);
+    assert_eq!(reader.read::<Shape>(), Err(BinaryError::UnknownVariant(9)));
+  }
+
+  #[cfg(feature = "derive")]
+  #[test]
+  fn test_derive_enum_by_name() {
+    #[derive(BinWrite, BinRead, Debug, PartialEq)]
+    #[binary(by_name)]
+    enum Level {
+      Low,
+      High(u8),
+    }
+
+    // A newer writer's enum with an extra variant, sharing the same names
+    #[derive(BinWrite, BinRead, Debug, PartialEq)]
+    #[bin(by_name)]
+    enum LevelV2 {
+      Medium,
+      High(u8),
+      Low,
+    }
+
+    let mut writer = BinaryWriter::new();
+    writer.write(&LevelV2::High(3)).write(&LevelV2::Low).write(&LevelV2::Medium);
+
+    let data = writer.get_data();
+    let mut reader = BinaryReader::new(&data);
+
+    // Variants are matched by name, so reordering does not break old readers
+    assert_eq!(reader.read_string().unwrap(), "High");
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read::<Level>().unwrap(), Level::High(3));
+    assert_eq!(reader.read::<Level>().unwrap(), Level::Low);
+
+    // A name the reader does not know is an error
+    assert_eq!(reader.read::<Level>(), Err(BinaryError::UnknownVariantName("Medium".to_string())));
+  }
+
+  #[test]
+  fn test_binary_writer_padding() {
+    let mut writer = BinaryWriter::new();
+
+    // Pad between two fields, with a count larger than one internal chunk
+    writer.write_u8(1).write_padding(100, 0xAA).write_u8(2).write_padding(0, 0xFF);
+
+    let data = writer.get_data();
+    assert_eq!(data.len(), 102);
+    assert_eq!(data[0], 1);
+    assert!(data[1..101].iter().all(|&b| b == 0xAA));
+    assert_eq!(data[101], 2);
+  }
+
+  #[test]
+  fn test_binary_writer_reader_cow_str() {
+    let borrowed: Cow<str> = Cow::Borrowed("Bin-It");
+    let owned: Cow<str> = Cow::Owned("Bin-It".to_string());
+
+    // Borrowed and owned strings produce identical bytes
+    let mut a = BinaryWriter::new();
+    a.write_cow_str(&borrowed);
+    let mut b = BinaryWriter::new();
+    b.write(&owned);
+    let data = a.get_data();
+    assert_eq!(data, b.get_data());
+
+    // Cows also work through the generic vector path
+    let mut writer = BinaryWriter::new();
+    writer.write_vec(&[borrowed.clone(), owned]);
+    let vec_data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+    let read: Cow<str> = reader.read().unwrap();
+    assert!(matches!(read, Cow::Owned(_)));
+    assert_eq!(read, borrowed);
+
+    let mut reader = BinaryReader::new(&vec_data);
+    assert_eq!(reader.read_vec::<Cow<str>>().unwrap(), vec!["Bin-It", "Bin-It"]);
+  }
+
+  #[test]
+  fn test_binary_writer_reserve_and_fill_length() {
+    let mut writer = BinaryWriter::new();
+
+    // Header, then a body whose length is only known after writing it
+    writer.write_u16(0xB1);
+    let slot = writer.reserve_length();
+    writer.write_string("body").write_u64(7);
+    writer.fill_length(slot).unwrap();
+    writer.write_u8(0xFF);
+
+    let data = writer.get_data();
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_u16().unwrap(), 0xB1);
+    let length = reader.read_u32().unwrap();
+    assert_eq!(length, 4 + 4 + 8);
+    assert_eq!(reader.read_string().unwrap(), "body");
+    assert_eq!(reader.read_u64().unwrap(), 7);
+    assert_eq!(reader.read_u8().unwrap(), 0xFF);
+
+    // Big-endian writers backpatch in their own byte order
+    let mut writer = BinaryWriter::new_with_endianness(Endianness::Big);
+    let slot = writer.reserve_length();
+    writer.write_u8(1);
+    writer.fill_length(slot).unwrap();
+    assert_eq!(writer.get_data(), vec![0, 0, 0, 1, 1]);
+
+    // A slot can only be filled by the writer that reserved it
+    let mut first = BinaryWriter::new();
+    let mut second = BinaryWriter::new();
+    let slot = first.reserve_length();
+    second.write_u32(0);
+    assert_eq!(second.fill_length(slot).err(), Some(BinaryError::ForeignLengthSlot));
+  }
+
+  #[test]
+  fn test_binary_reader_expect_bytes() {
+    let mut data = b"BINI".to_vec();
+    data.extend_from_slice(&3u16.to_le_bytes());
+
+    // A matching magic advances past it to the version
+    let mut reader = BinaryReader::new(&data);
+    reader.expect_bytes(b"BINI").unwrap();
+    assert_eq!(reader.read_u16().unwrap(), 3);
+
+    // A mismatch reports both sides and leaves the cursor alone
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(
+      reader.expect_bytes(b"BINX"),
+      Err(BinaryError::MagicMismatch { expected: b"BINX".to_vec(), found: b"BINI".to_vec() })
+    );
+    assert_eq!(reader.remaining(), 6);
+
+    // Too few bytes is an EOF, not a mismatch
+    let mut reader = BinaryReader::new(&data[..2]);
+    assert_eq!(reader.expect_bytes(b"BINI"), Err(BinaryError::UnexpectedEof { needed: 4, available: 2, offset: 0 }));
+  }
+
+  #[test]
+  fn test_binary_reader_read_map_into() {
+    let first: HashMap<u32, String> = [(1, "one".to_string()), (2, "two".to_string())].into_iter().collect();
+    let second: BTreeMap<u32, String> = [(2, "deux".to_string()), (3, "trois".to_string())].into_iter().collect();
+
+    // HashMap and BTreeMap share an encoding, so either can be merged into the other
+    let mut writer = BinaryWriter::new();
+    writer.write(&first).write(&second);
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+    let mut merged: HashMap<u32, String> = HashMap::new();
+    assert_eq!(reader.read_map_into(&mut merged).unwrap(), 2);
+    assert_eq!(reader.read_map_into(&mut merged).unwrap(), 2);
+
+    // The union, with the later map winning on key 2
+    assert_eq!(merged.len(), 3);
+    assert_eq!(merged[&1], "one");
+    assert_eq!(merged[&2], "deux");
+    assert_eq!(merged[&3], "trois");
+
+    // Whole maps round-trip through the traits
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read::<HashMap<u32, String>>().unwrap(), first);
+    assert_eq!(reader.read::<BTreeMap<u32, String>>().unwrap(), second);
+
+    // A huge count of zero-sized entries is rejected instead of merged one empty read at a time
+    let data = u32::MAX.to_le_bytes();
+    let overflow = BinaryError::LengthOverflow { length: u32::MAX as usize, max: MAX_ZERO_SIZED_LEN as u64 };
+    assert_eq!(BinaryReader::new(&data).read::<HashMap<(), ()>>(), Err(overflow.clone()));
+    assert_eq!(BinaryReader::new(&data).read::<BTreeMap<(), PhantomData<u8>>>(), Err(overflow));
+    // Only one side being zero-sized is bounded by the input as usual
+    assert!(matches!(BinaryReader::new(&data).read::<BTreeMap<(), u8>>(), Err(BinaryError::UnexpectedEof { .. })));
+  }
+
+  #[test]
+  fn test_binary_reader_read_str_and_cow_str() {
+    let mut writer = BinaryWriter::new();
+    writer.write_string("Hello").write_cow_str(&Cow::Owned("World".to_string()));
+    let data = writer.get_data();
+
+    // Both reads borrow straight from the buffer
+    let mut reader = BinaryReader::new(&data);
+    let hello = reader.read_str().unwrap();
+    assert_eq!(hello, "Hello");
+    assert_eq!(hello.as_ptr(), data[4..].as_ptr());
+    let world = reader.read_cow_str().unwrap();
+    assert!(matches!(world, Cow::Borrowed("World")));
+
+    // Invalid UTF-8 is still rejected
+    let bad = [2, 0, 0, 0, 0xFF, 0xFE];
+    let mut reader = BinaryReader::new(&bad);
+    assert!(matches!(reader.read_cow_str(), Err(BinaryError::InvalidUtf8 { .. })));
+  }
+
+  #[test]
+  fn test_binary_writer_trace() {
+    let mut writer = BinaryWriter::with_trace();
+    writer.write_u32(42).write_string("hi").write_bool(true).write_vec_u16(&[1, 2, 3]).write_f64_be(0.5);
+    writer.write(&vec!["a".to_string(), "b".to_string()]);
+    writer.write_vec_u8(&[7, 8]).write_bytes("xyz");
+
+    // Length prefixes and vector elements are folded into their value's token
+    assert_eq!(
+      writer.trace(),
+      &[
+        Token::U32(42),
+        Token::Str("hi".to_string()),
+        Token::Bool(true),
+        Token::Vec(3),
+        Token::F64(0.5),
+        Token::Vec(2),
+        Token::Vec(2),
+        Token::Vec(3),
+      ]
+    );
+    let rendered: Vec<String> = writer.trace().iter().map(|t| t.to_string()).collect();
+    assert_eq!(rendered.join(" "), "u32(42) str(\"hi\") bool(true) vec(3) f64(0.5) vec(2) vec(2) vec(3)");
+
+    // Tracing does not change the bytes, and is off by default
+    let data = writer.get_data();
+    let mut plain = BinaryWriter::new();
+    plain.write_u32(42).write_string("hi").write_bool(true).write_vec_u16(&[1, 2, 3]).write_f64_be(0.5);
+    plain.write(&vec!["a".to_string(), "b".to_string()]);
+    plain.write_vec_u8(&[7, 8]).write_bytes("xyz");
+    assert!(plain.trace().is_empty());
+    assert_eq!(plain.get_data(), data);
+  }
+
+  #[test]
+  fn test_binary_writer_reader_sets() {
+    let ids: BTreeSet<u32> = [9, 3, 7, 1].into_iter().collect();
+    let hashed: HashSet<u32> = ids.iter().copied().collect();
+
+    // Sorted writes of equal sets are byte-identical, whichever set type holds them
+    let mut a = BinaryWriter::new();
+    a.write_set(&ids);
+    let mut b = BinaryWriter::new();
+    b.write_hash_set_sorted(&hashed);
+    let data = a.get_data();
+    assert_eq!(data, b.get_data());
+    assert_eq!(data, [4, 0, 0, 0, 1, 0, 0, 0, 3, 0, 0, 0, 7, 0, 0, 0, 9, 0, 0, 0]);
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_set::<u32>(true).unwrap(), ids);
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_hash_set::<u32>(true).unwrap(), hashed);
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read::<HashSet<u32>>().unwrap(), hashed);
+
+    // Duplicates merge by default and error when rejected
+    let mut writer = BinaryWriter::new();
+    writer.write_vec_u32(&[5, 6, 5]);
+    let dup = writer.get_data();
+    let mut reader = BinaryReader::new(&dup);
+    assert_eq!(reader.read_set::<u32>(false).unwrap().len(), 2);
+    let mut reader = BinaryReader::new(&dup);
+    assert_eq!(reader.read_hash_set::<u32>(true), Err(BinaryError::DuplicateElement { index: 2 }));
+
+    // A huge count of zero-sized elements is rejected instead of merged one empty read at a time
+    let data = u32::MAX.to_le_bytes();
+    let overflow = BinaryError::LengthOverflow { length: u32::MAX as usize, max: MAX_ZERO_SIZED_LEN as u64 };
+    assert_eq!(BinaryReader::new(&data).read::<BTreeSet<()>>(), Err(overflow.clone()));
+    assert_eq!(BinaryReader::new(&data).read_set::<()>(false), Err(overflow.clone()));
+    assert_eq!(BinaryReader::new(&data).read::<HashSet<PhantomData<u8>>>(), Err(overflow));
+  }
+
+  #[test]
+  fn test_binary_writer_reader_version_header() {
+    let mut writer = BinaryWriter::new_versioned(3);
+    writer.write_string("payload");
+    let data = writer.get_data();
+    assert_eq!(&data[..2], &[3, 0]);
+
+    // Decoding can branch on the version
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_version().unwrap(), 3);
+    assert_eq!(reader.read_string().unwrap(), "payload");
+
+    // The range is inclusive on both ends
+    assert_eq!(BinaryReader::new(&data).require_version(1, 3).unwrap(), 3);
+    assert_eq!(BinaryReader::new(&data).require_version(3, 5).unwrap(), 3);
+    assert_eq!(
+      BinaryReader::new(&data).require_version(4, 5),
+      Err(BinaryError::UnsupportedVersion { version: 3, min: 4, max: 5 })
+    );
+  }
+
+  #[test]
+  fn test_binary_writer_reader_bytes_u8len() {
+    let salt: Vec<u8> = (0..200).collect();
+
+    let mut writer = BinaryWriter::new();
+    writer.write_bytes_u8len(&salt).unwrap().write_u8(0xEE);
+    let data = writer.get_data();
+    assert_eq!(data.len(), 1 + 200 + 1);
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_bytes_u8len().unwrap(), salt);
+    assert_eq!(reader.read_u8().unwrap(), 0xEE);
+
+    // More than 255 bytes errors and writes nothing
+    let mut writer = BinaryWriter::new();
+    assert_eq!(
+      writer.write_bytes_u8len(&[0; 300]).err(),
+      Some(BinaryError::LengthOverflow { length: 300, max: 255 })
+    );
+    assert!(writer.get_data().is_empty());
+
+    // A length past the end of the buffer is an EOF
+    let mut reader = BinaryReader::new(&[5, 1, 2]);
+    assert_eq!(reader.read_bytes_u8len(), Err(BinaryError::UnexpectedEof { needed: 5, available: 2, offset: 1 }));
+  }
+
+  #[test]
+  #[cfg(feature = "derive")]
+  fn test_derive_try_from_struct() {
+    #[derive(BinWrite, BinRead, Debug, PartialEq)]
+    #[bin(try_from)]
+    struct Header {
+      magic: u32,
+      version: u16,
+      flags: u16,
+      length: u32,
+    }
+
+    let header = Header { magic: 0xB1B1, version: 2, flags: 1, length: 64 };
+    let mut writer = BinaryWriter::new();
+    writer.write(&header).write_u8(0xFF);
+    let buf = writer.get_data();
+
+    // A 12-byte slice converts directly
+    assert_eq!(Header::try_from(&buf[..12]).unwrap(), header);
+
+    // Any other length is rejected, including one that would otherwise decode
+    assert_eq!(Header::try_from(&buf[..]), Err(BinaryError::SizeMismatch { expected: 12, found: 13 }));
+    assert_eq!(Header::try_from(&buf[..8]), Err(BinaryError::SizeMismatch { expected: 12, found: 8 }));
+
+    // The size comes from `SERIALIZED_SIZE`, so nested derived structs and markers count too
+    #[derive(BinWrite, BinRead, Debug, PartialEq)]
+    #[bin(try_from)]
+    struct Packet {
+      header: Header,
+      kind: u8,
+      marker: PhantomData<u64>,
+    }
+
+    let packet = Packet { header, kind: 3, marker: PhantomData };
+    let mut writer = BinaryWriter::new();
+    writer.write(&packet);
+    let buf = writer.get_data();
+    assert_eq!(Packet::try_from(&buf[..]).unwrap(), packet);
+    assert_eq!(Packet::try_from(&buf[..12]), Err(BinaryError::SizeMismatch { expected: 13, found: 12 }));
+  }
+
+  #[test]
+  fn test_binary_reader_try_read_rewinds() {
+    // A string whose length prefix claims more bytes than remain
+    let data = [10, 0, 0, 0, b'h', b'i'];
+    let mut reader = BinaryReader::new(&data);
+
+    // The failed attempt leaves the cursor untouched
+    assert_eq!(reader.try_read(|r| r.read_string()), Err(BinaryError::UnexpectedEof { needed: 10, available: 2, offset: 4 }));
+    assert_eq!(reader.remaining(), 6);
+
+    // So a fallback interpretation sees the same bytes
+    let (length, rest) = reader.try_read(|r| Ok((r.read_u32()?, r.read_remaining_vec()))).unwrap();
+    assert_eq!(length, 10);
+    assert_eq!(rest, b"hi");
+  }
+
+  #[test]
+  fn test_binary_reader_read_string_lossy() {
+    // "ab", an invalid byte, then "c"
+    let data = [4, 0, 0, 0, b'a', b'b', 0xFF, b'c'];
+
+    let mut reader = BinaryReader::new(&data);
+    assert!(matches!(reader.read_string(), Err(BinaryError::InvalidUtf8 { .. })));
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_string_lossy().unwrap(), "ab\u{FFFD}c");
+    assert_eq!(reader.remaining(), 0);
+  }
+
+  #[test]
+  fn test_binary_writer_canonicalize_nan() {
+    let odd_nan32 = f32::from_bits(0xffc0_1234);
+    let odd_nan64 = f64::from_bits(0x7ff0_0000_0000_0001);
+
+    // By default NaN payloads are written as-is
+    let mut writer = BinaryWriter::new();
+    writer.write_f32(odd_nan32);
+    assert_eq!(writer.get_data(), 0xffc0_1234u32.to_le_bytes());
+
+    let mut writer = BinaryWriter::new();
+    writer.canonicalize_nan(true);
+    writer.write_f32(odd_nan32).write_f64_be(odd_nan64).write_f64(-2.5);
+    writer.write_pairs_u32_f64(&[1], &[odd_nan64]).unwrap();
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_f32_bits().unwrap(), 0x7fc0_0000);
+    assert_eq!(reader.read_u64_be().unwrap(), 0x7ff8_0000_0000_0000);
+    // Non-NaN values are untouched
+    assert_eq!(reader.read_f64().unwrap(), -2.5);
+    let (_, vals) = reader.read_pairs_u32_f64().unwrap();
+    assert_eq!(vals[0].to_bits(), 0x7ff8_0000_0000_0000);
+  }
+
+  #[test]
+  fn test_binary_reader_take_reader() {
+    let mut writer = BinaryWriter::new();
+    writer.write_u16(7).write_u16(8).write_u32(99);
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+    let mut sub = reader.take_reader(4).unwrap();
+
+    // The sub-reader is bounded to its own bytes
+    assert_eq!(sub.read_u16().unwrap(), 7);
+    assert_eq!(sub.read_u16().unwrap(), 8);
+    assert_eq!(sub.read_u32(), Err(BinaryError::UnexpectedEof { needed: 4, available: 0, offset: 4 }));
+
+    // The parent continues after them
+    assert_eq!(reader.read_u32().unwrap(), 99);
+    assert_eq!(reader.take_reader(1).err(), Some(BinaryError::UnexpectedEof { needed: 1, available: 0, offset: 8 }));
+  }
+
+  #[test]
+  fn test_binary_writer_reader_messages() {
+    let mut first = BinaryWriter::new();
+    first.write_u32(1).write_string("one");
+    let mut second = BinaryWriter::new();
+    second.write_bool(true);
+
+    let mut log = BinaryWriter::new();
+    log.write_message(&first.get_data()).write_message(&second.get_data()).write_message(&[]);
+    let data = log.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+    let messages: Vec<&[u8]> = reader.messages().collect::<Result<_, _>>().unwrap();
+    assert_eq!(messages.len(), 3);
+    assert_eq!(messages[2], b"");
+
+    // Each message decodes on its own
+    let mut message = BinaryReader::new(messages[0]);
+    assert_eq!(message.read_u32().unwrap(), 1);
+    assert_eq!(message.read_string().unwrap(), "one");
+    assert!(BinaryReader::new(messages[1]).read_bool().unwrap());
+
+    // A truncated message yields one error and ends the iteration
+    let mut reader = BinaryReader::new(&data[..data.len() - 6]);
+    let results: Vec<_> = reader.messages().collect();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[1], Err(BinaryError::UnexpectedEof { needed: 4, available: 3, offset: 15 }));
+  }
+
+  #[test]
+  fn test_binary_writer_reader_framing() {
+    let mut writer = BinaryWriter::new();
+    writer.write_u16(0xABCD).write_string("ping");
+    let framed = writer.finish_framed().unwrap();
+    assert_eq!(&framed[..4], &[10, 0, 0, 0]);
+
+    // An exact match yields a reader over just the payload
+    let mut reader = BinaryReader::new(&framed);
+    let mut frame = reader.read_frame().unwrap();
+    assert_eq!(frame.read_u16().unwrap(), 0xABCD);
+    assert_eq!(frame.read_string().unwrap(), "ping");
+    assert_eq!(frame.remaining(), 0);
+    assert_eq!(reader.remaining(), 0);
+
+    // A declared length past the end of the buffer is an error
+    let mut reader = BinaryReader::new(&framed[..framed.len() - 1]);
+    assert_eq!(reader.read_frame().err(), Some(BinaryError::UnexpectedEof { needed: 10, available: 9, offset: 4 }));
+    assert_eq!(reader.remaining(), framed.len() - 1);
+
+    // Narrower prefixes are available too
+    let mut writer = BinaryWriter::new();
+    writer.write_u8(5);
+    let framed = writer.finish_framed_with_prefix(PrefixWidth::U8).unwrap();
+    assert_eq!(framed, [1, 5]);
+    let mut reader = BinaryReader::new(&framed);
+    assert_eq!(reader.read_frame_with_prefix(PrefixWidth::U8).unwrap().read_u8().unwrap(), 5);
+  }
+
+  #[test]
+  fn test_binary_reader_near_max_lengths() {
+    // A reader part-way through its buffer, where cursor + size would wrap
+    let data = [0u8; 8];
+    let mut reader = BinaryReader::new(&data);
+    reader.read_u32().unwrap();
+    let eof = |needed| Err(BinaryError::UnexpectedEof { needed, available: 4, offset: 4 });
+    assert_eq!(reader.ensure_available(usize::MAX), eof(usize::MAX));
+    assert_eq!(reader.ensure_available(usize::MAX - 3), eof(usize::MAX - 3));
+    assert_eq!(reader.ensure_available(4), Ok(()));
+
+    // Declared lengths of u32::MAX fail cleanly for every length-prefixed read
+    let mut huge = u32::MAX.to_le_bytes().to_vec();
+    huge.extend_from_slice(&[0; 8]);
+    let eof = Some(BinaryError::UnexpectedEof { needed: u32::MAX as usize, available: 8, offset: 4 });
+    assert_eq!(BinaryReader::new(&huge).read_string().err(), eof);
+    assert_eq!(BinaryReader::new(&huge).read_vec_u8().err(), eof);
+    assert!(matches!(BinaryReader::new(&huge).read_vec_u64(), Err(BinaryError::UnexpectedEof { available: 8, .. })));
+    assert_eq!(BinaryReader::new(&huge).skip_string().err(), eof);
+    assert!(matches!(BinaryReader::new(&huge).skip_vec::<u64>(), Err(BinaryError::UnexpectedEof { available: 8, .. })));
+    assert_eq!(BinaryReader::new(&huge).read_message().err(), eof);
+    assert!(BinaryReader::new(&huge).read_packed_strings().is_err());
+    assert!(BinaryReader::new(&huge).read_vec_string().is_err());
+  }
+
+  #[test]
+  fn test_binary_reader_signed_big_endian() {
+    let mut writer = BinaryWriter::new_with_endianness(Endianness::Big);
+    writer.write_i16(-2).write_i32(-300_000).write_i64(i64::MIN + 1).write_i8(-128);
+    let data = writer.get_data();
+    assert_eq!(&data[..2], &[0xFF, 0xFE]);
+
+    let mut reader = BinaryReader::new_with_endianness(&data, Endianness::Big);
+    assert_eq!(reader.read_i16().unwrap(), -2);
+    assert_eq!(reader.read_i32().unwrap(), -300_000);
+    assert_eq!(reader.read_i64().unwrap(), i64::MIN + 1);
+    assert_eq!(reader.read_i8().unwrap(), -128);
+  }
+
+  #[test]
+  fn test_binary_writer_reader_vec_bytes() {
+    let chain = vec![vec![0x30, 0x82, 0x01], vec![], vec![0xFF; 40]];
+
+    let mut writer = BinaryWriter::new();
+    writer.write_vec_bytes(&chain);
+    let data = writer.get_data();
+    assert_eq!(data.len(), 4 + (4 + 3) + 4 + (4 + 40));
+
+    // Same layout as the generic Vec<Vec<u8>> encoding
+    let mut generic = BinaryWriter::new();
+    generic.write(&chain);
+    assert_eq!(generic.get_data(), data);
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_vec_bytes().unwrap(), chain);
+  }
+
+  #[test]
+  fn test_size_hint_matches_written_length() {
+    let values: Vec<u32> = vec![1, 2, 3];
+    let name = "Bin-It".to_string();
+    let hint = 7u16.serialized_size() + name.serialized_size() + values.serialized_size();
+    assert_eq!(hint, 2 + (4 + 6) + (4 + 12));
+
+    // Sized exactly, the buffer never grows
+    let mut writer = BinaryWriter::with_capacity(hint);
+    writer.write_u16(7).write(&name).write(&values);
+    let data = writer.get_data();
+    assert_eq!(data.len(), hint);
+    assert_eq!(data.capacity(), hint);
+
+    let nested: Vec<Vec<String>> = vec![vec!["a".to_string()], vec![]];
+    let mut writer = BinaryWriter::new();
+    writer.write(&nested);
+    assert_eq!(writer.get_data().len(), nested.serialized_size());
+  }
+
+  #[test]
+  #[cfg(feature = "derive")]
+  fn test_derive_size_hint() {
+    #[derive(BinWrite, SizeHint)]
+    struct Record {
+      id: u64,
+      name: String,
+      scores: Vec<u32>,
+    }
+
+    #[derive(BinWrite, SizeHint)]
+    #[bin(by_name)]
+    enum Event {
+      Created(Record),
+      Deleted { id: u64 },
+    }
+
+    let record = Record { id: 9, name: "widget".to_string(), scores: vec![10, 20, 30] };
+    let mut writer = BinaryWriter::new();
+    writer.write(&record);
+    assert_eq!(record.serialized_size(), writer.get_data().len());
+
+    for event in [Event::Created(record), Event::Deleted { id: 9 }] {
+      let mut writer = BinaryWriter::new();
+      writer.write(&event);
+      assert_eq!(event.serialized_size(), writer.get_data().len());
+    }
+  }
+
+  #[test]
+  #[cfg(feature = "derive")]
+  fn test_derive_serialized_size_const() {
+    #[derive(BinWrite, BinRead)]
+    struct Fixed {
+      a: u32,
+      b: (),
+      c: Box<f64>,
+      d: char,
+    }
+
+    #[derive(BinWrite, BinRead)]
+    struct Nested {
+      inner: Fixed,
+      flag: bool,
+    }
+
+    #[derive(BinWrite, BinRead)]
+    struct Marker;
+
+    #[derive(BinWrite, BinRead)]
+    struct Variable {
+      id: u16,
+      name: String,
+    }
+
+    #[derive(BinWrite, BinRead)]
+    enum SameSize {
+      A(u32),
+      B(i32),
+    }
+
+    #[derive(BinWrite, BinRead)]
+    enum Mixed {
+      A(u32),
+      B(u8),
+    }
+
+    const FIXED: usize = match Fixed::SERIALIZED_SIZE {
+      Some(n) => n,
+      None => 0,
+    };
+    assert_eq!(FIXED, 16);
+    assert_eq!(Nested::SERIALIZED_SIZE, Some(17));
+    assert_eq!(Marker::SERIALIZED_SIZE, Some(0));
+    assert_eq!(Variable::SERIALIZED_SIZE, None);
+    assert_eq!(SameSize::SERIALIZED_SIZE, Some(8));
+    assert_eq!(Mixed::SERIALIZED_SIZE, None);
+    assert_eq!(String::SERIALIZED_SIZE, None);
+
+    // The constant matches what is actually written
+    let mut writer = BinaryWriter::with_capacity(Nested::SERIALIZED_SIZE.unwrap());
+    writer.write(&Nested { inner: Fixed { a: 1, b: (), c: Box::new(2.0), d: 'x' }, flag: true });
+    assert_eq!(writer.get_data().len(), 17);
+  }
+
+  #[test]
+  fn test_binary_reader_read_tuple() {
+    let mut writer = BinaryWriter::new();
+    writer.write_u32(7).write_string("seven").write_bool(true);
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+    let (a, b, c): (u32, String, bool) = reader.read_tuple().unwrap();
+    assert_eq!((a, b.as_str(), c), (7, "seven", true));
+
+    // Tuples write the same bytes back, and report their size
+    let mut writer = BinaryWriter::new();
+    writer.write(&(a, b, c));
+    assert_eq!(writer.get_data(), data);
+    assert_eq!(<(u32, bool, char)>::SERIALIZED_SIZE, Some(9));
+    assert_eq!(<(u32, String)>::SERIALIZED_SIZE, None);
+
+    // The first failing element ends the read
+    let mut reader = BinaryReader::new(&data[..6]);
+    assert_eq!(
+      reader.read_tuple::<(u32, String, bool)>(),
+      Err(BinaryError::UnexpectedEof { needed: 4, available: 2, offset: 4 })
+    );
+  }
+
+  #[test]
+  fn test_binary_reader_finite_floats() {
+    let mut writer = BinaryWriter::new();
+    writer.write_f32(0.25).write_f32(f32::NAN).write_f64(-1e300).write_f64(f64::INFINITY);
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_f32_finite().unwrap(), 0.25);
+    assert!(matches!(reader.read_f32_finite(), Err(BinaryError::InvalidValue(_))));
+    assert_eq!(reader.read_f64_finite().unwrap(), -1e300);
+    assert_eq!(
+      reader.read_f64_finite(),
+      Err(BinaryError::InvalidValue("expected a finite f64, found inf".to_string()))
+    );
+
+    // The plain readers stay permissive
+    let mut reader = BinaryReader::new(&data[4..]);
+    assert!(reader.read_f32().unwrap().is_nan());
+  }
+
+  #[test]
+  #[cfg(feature = "derive")]
+  fn test_derive_vec_of_structs() {
+    #[derive(BinWrite, BinRead, Debug, PartialEq, Clone)]
+    struct Sample {
+      at: u64,
+      value: f32,
+    }
+
+    #[derive(BinWrite, BinRead, Debug, PartialEq)]
+    struct Series {
+      name: String,
+      samples: Vec<Sample>,
+    }
+
+    let samples = vec![
+      Sample { at: 1, value: 0.5 },
+      Sample { at: 2, value: 1.5 },
+      Sample { at: 3, value: -2.0 },
+    ];
+    let series = Series { name: "temp".to_string(), samples: samples.clone() };
+
+    let mut writer = BinaryWriter::new();
+    writer.write_vec(&samples).write(&series);
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_vec::<Sample>().unwrap(), samples);
+    assert_eq!(reader.read::<Series>().unwrap(), series);
+    assert_eq!(reader.remaining(), 0);
+
+    // A count larger than the buffer can hold is rejected before decoding, leaving the cursor alone
+    let mut bogus = 1000u32.to_le_bytes().to_vec();
+    bogus.extend_from_slice(&data[4..40]);
+    let mut reader = BinaryReader::new(&bogus);
+    assert_eq!(reader.read_vec::<Sample>(), Err(BinaryError::UnexpectedEof { needed: 12000, available: 36, offset: 4 }));
+    assert_eq!(reader.remaining(), bogus.len());
+  }
+
+  #[test]
+  #[cfg(feature = "debug-schema")]
+  fn test_schema_trace_finds_divergence() {
+    let mut writer = BinaryWriter::new();
+    writer.write_u16(1).write_string("name").write_vec_u32(&[1, 2]).write_f64(0.5);
+    assert_eq!(writer.schema_trace(), vec![("u16", 0), ("str", 2), ("vec", 10), ("f64", 22)]);
+    let data = writer.get_data();
+
+    // A reader that agrees on the layout produces the same trace
+    let mut reader = BinaryReader::new(&data);
+    reader.read_u16().unwrap();
+    reader.read_string().unwrap();
+    reader.read_vec_u32().unwrap();
+    reader.read_f64().unwrap();
+    assert_eq!(reader.schema_trace(), vec![("u16", 0), ("str", 2), ("vec", 10), ("f64", 22)]);
+
+    // One that reads a u32 where a u16 was written diverges at the very first entry
+    let mut reader = BinaryReader::new(&data);
+    reader.read_u32().unwrap();
+    let _ = reader.try_read(|r| r.read_string());
+    reader.read_u16().unwrap();
+    assert_eq!(reader.schema_trace(), vec![("u32", 0), ("u16", 4)]);
+  }
+
+  #[test]
+  #[cfg(feature = "debug-schema")]
+  fn test_schema_trace_records_collections() {
+    let map: BTreeMap<u8, u16> = [(1, 2), (3, 4)].into_iter().collect();
+    let set: HashSet<u32> = [5, 6].into_iter().collect();
+    let mut writer = BinaryWriter::new();
+    writer.write(&map).write(&set);
+    let data = writer.get_data();
+
+    // Sets and maps read through `Readable` show up as one entry each, like their helpers
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read::<BTreeMap<u8, u16>>().unwrap(), map);
+    assert_eq!(reader.read::<HashSet<u32>>().unwrap(), set);
+    assert_eq!(reader.schema_trace(), vec![("map", 0), ("vec", 10)]);
+  }
+
+  #[test]
+  fn test_binary_writer_reader_result() {
+    let ok: Result<u32, String> = Ok(7);
+    let err: Result<u32, String> = Err("boom".into());
+
+    let mut writer = BinaryWriter::new();
+    writer.write_result(&ok).write_result(&err);
+    writer.write_vec(&[ok.clone(), err.clone()]);
+    let data = writer.get_data();
+    assert_eq!(&data[..5], &[0, 7, 0, 0, 0]);
+    assert_eq!(ok.serialized_size() + err.serialized_size(), 5 + 9);
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_result::<u32, String>().unwrap(), ok);
+    assert_eq!(reader.read_result::<u32, String>().unwrap(), err);
+    assert_eq!(reader.read_vec::<Result<u32, String>>().unwrap(), vec![ok, err]);
+
+    // Any other tag is rejected
+    let mut reader = BinaryReader::new(&[2, 0, 0, 0, 0]);
+    assert!(matches!(reader.read_result::<u32, String>(), Err(BinaryError::InvalidValue(_))));
+  }
+
+  #[test]
+  fn test_binary_writer_from_vec() {
+    let header = b"HDR\x01".to_vec();
+    let mut writer = BinaryWriter::from_vec(header);
+    writer.write_u16(0xBEEF);
+
+    // Length slots are relative to the whole buffer
+    let slot = writer.reserve_length();
+    writer.write_u8(9);
+    writer.fill_length(slot).unwrap();
+
+    let data = writer.get_data();
+    assert_eq!(data, [b'H', b'D', b'R', 1, 0xEF, 0xBE, 1, 0, 0, 0, 9]);
+  }
+
+  #[test]
+  fn test_binary_reader_remaining_slice() {
+    let data = [1, 0, 0, 0, 0xAA, 0xBB, 0xCC];
+    let mut reader = BinaryReader::new(&data);
+    reader.read_u32().unwrap();
+
+    // Borrowed from the original buffer, and the cursor stays put
+    let tail = reader.remaining_slice();
+    assert_eq!(tail.len(), data.len() - 4);
+    assert_eq!(tail.as_ptr(), data[4..].as_ptr());
+    assert_eq!(reader.read_u8().unwrap(), 0xAA);
+    assert_eq!(reader.remaining_slice(), &[0xBB, 0xCC]);
+  }
+
+  #[test]
+  fn test_checked_byte_len() {
+    let mut reader = BinaryReader::new(&[0; 7]);
+    reader.read_u16().unwrap();
+    assert_eq!(reader.checked_byte_len(3, 8), Ok(24));
+    assert_eq!(reader.checked_byte_len(0, usize::MAX), Ok(0));
+
+    // A product past usize::MAX errors instead of wrapping
+    let eof = Err(BinaryError::UnexpectedEof { needed: usize::MAX, available: 5, offset: 2 });
+    assert_eq!(reader.checked_byte_len(usize::MAX / 8 + 1, 8), eof);
+    assert_eq!(reader.checked_byte_len(usize::MAX, 2), eof);
+  }
+
+  #[test]
+  #[cfg(feature = "derive")]
+  fn test_derive_phantom_data_fields() {
+    // `Unit` implements neither trait; it only appears inside PhantomData
+    #[derive(Debug, PartialEq)]
+    struct Unit;
+
+    #[derive(Binary, SizeHint, Debug, PartialEq)]
+    struct Tagged<T, U> {
+      id: u32,
+      value: U,
+      marker: PhantomData<T>,
+      callback: PhantomData<fn() -> T>,
+      nothing: (),
+    }
+
+    let tagged: Tagged<Unit, u16> = Tagged { id: 5, value: 6, marker: PhantomData, callback: PhantomData, nothing: () };
+    let mut writer = BinaryWriter::new();
+    writer.write(&tagged);
+    let data = writer.get_data();
+
+    // Only the real fields take up bytes
+    assert_eq!(data, [5, 0, 0, 0, 6, 0]);
+    assert_eq!(tagged.serialized_size(), 6);
+    assert_eq!(<Tagged<Unit, u16>>::SERIALIZED_SIZE, Some(6));
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read::<Tagged<Unit, u16>>().unwrap(), tagged);
+  }
+
+  #[cfg(feature = "derive")]
+  #[test]
+  fn test_derive_u8_tag() {
+    #[derive(BinWrite, BinRead, SizeHint, Debug, PartialEq)]
+    #[bin(tag = "u8")]
+    enum Message {
+      Ping,
+      Data(u16),
+    }
+
+    let mut writer = BinaryWriter::new();
+    writer.write(&Message::Data(7));
+    // One tag byte, then the u16 payload.
+    assert_eq!(writer.get_data(), &[1, 7, 0]);
+    assert_eq!(Message::Data(7).serialized_size(), 3);
+    assert_eq!(<Message as Readable>::SERIALIZED_SIZE, None);
+
+    let mut reader = BinaryReader::new(&[1, 7, 0]);
+    assert_eq!(reader.read::<Message>().unwrap(), Message::Data(7));
+    let mut reader = BinaryReader::new(&[0]);
+    assert_eq!(reader.read::<Message>().unwrap(), Message::Ping);
+    let mut reader = BinaryReader::new(&[2]);
+    assert!(matches!(reader.read::<Message>(), Err(BinaryError::UnknownVariant(2))));
+  }
+
+  #[test]
+  fn test_read_vec_bounded() {
+    let mut writer = BinaryWriter::new();
+    writer.write_vec_u32(&[1, 2, 3]);
+    writer.write_vec_string(&["a".to_string(), "b".to_string()]);
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_vec_u32_bounded(3).unwrap(), vec![1, 2, 3]);
+    assert_eq!(reader.read_vec_string_bounded(2).unwrap(), vec!["a", "b"]);
+
+    // A length above the caller's bound is rejected without reading the elements.
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(
+      reader.read_vec_u32_bounded(2).err(),
+      Some(BinaryError::LengthOverflow { length: 3, max: 2 })
+    );
+    let mut reader = BinaryReader::new(&[0xE8, 0x03, 0, 0]);
+    assert_eq!(
+      reader.read_vec_u8_bounded(999).err(),
+      Some(BinaryError::LengthOverflow { length: 1000, max: 999 })
+    );
+  }
+
+  #[test]
+  fn test_ranges() {
+    let mut writer = BinaryWriter::new();
+    writer.write_range(&(3u32..10)).write_range_inclusive(&(0i64..=100));
+    let data = writer.get_data();
+    assert_eq!(&data[..8], &[3, 0, 0, 0, 10, 0, 0, 0]);
+    assert_eq!(data.len(), 8 + 16);
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_range::<u32>().unwrap(), 3..10);
+    assert_eq!(reader.read_range_inclusive::<i64>().unwrap(), 0..=100);
+
+    // Ranges also work through the traits, e.g. as vector elements.
+    let ranges = vec![0u16..1, 5..9];
+    let mut writer = BinaryWriter::new();
+    writer.write(&ranges);
+    let data = writer.get_data();
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read::<Vec<Range<u16>>>().unwrap(), ranges);
+    assert_eq!(<RangeInclusive<u64> as Readable>::SERIALIZED_SIZE, Some(16));
+  }
+
+  #[test]
+  fn test_serialized_data() {
+    let mut writer = BinaryWriter::new();
+    writer.write_u16(7).write_string("hi");
+    let data = writer.get_data_typed();
+    assert_eq!(data.len(), 8);
+    assert_eq!(data.as_ref(), &[7, 0, 2, 0, 0, 0, b'h', b'i']);
+
+    let mut reader = data.reader();
+    assert_eq!(reader.read_u16().unwrap(), 7);
+    assert_eq!(reader.read_string().unwrap(), "hi");
+
+    let bytes: Vec<u8> = data.clone().into();
+    assert_eq!(SerializedData::from(bytes), data);
+  }
+
+  #[test]
+  fn test_read_bool_invalid() {
+    let mut reader = BinaryReader::new(&[1, 2]);
+    assert!(reader.read_bool().unwrap());
+    assert!(matches!(reader.read_bool(), Err(BinaryError::InvalidValue(_))));
+  }
+
+  #[cfg(feature = "fuzzing")]
+  #[test]
+  fn test_try_decode_all_adversarial() {
+    let inputs: &[&[u8]] = &[
+      &[],
+      &[2],
+      &[0xFF; 3],
+      &[0xFF; 64],
+      // A huge length prefix followed by almost nothing.
+      &[0xFF, 0xFF, 0xFF, 0x7F, 1, 2],
+      // An invalid code point and invalid UTF-8.
+      &[0x00, 0xD8, 0x00, 0x00, 2, 0, 0, 0, 0xC3, 0x28],
+      &[1, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF],
+    ];
+    for input in inputs {
+      try_decode_all(input);
+    }
+  }
+
+  #[test]
+  fn test_read_float_vectors_across_endianness() {
+    let doubles = [1.5, -0.25, f64::MAX];
+    let floats = [3.0f32, f32::MIN_POSITIVE];
+    let mut writer = BinaryWriter::new_with_endianness(Endianness::Big);
+    writer.write_vec_f64(&doubles).write_vec_f32(&floats);
+    let big = writer.get_data();
+    assert_eq!(&big[..12], &[0, 0, 0, 3, 0x3F, 0xF8, 0, 0, 0, 0, 0, 0]);
+
+    // A default reader decodes the big-endian arrays in one call each
+    let mut reader = BinaryReader::new(&big);
+    assert_eq!(reader.read_vec_f64_be().unwrap(), doubles);
+    assert_eq!(reader.read_vec_f32_be().unwrap(), floats);
+    // and is left in its own byte order afterwards.
+    let mut reader = BinaryReader::new(&[1, 0]);
+    reader.read_vec_f64_be().unwrap_err();
+    assert_eq!(reader.endianness(), Endianness::Little);
+
+    let mut writer = BinaryWriter::new();
+    writer.write_vec_f64(&doubles);
+    let little = writer.get_data();
+    let mut reader = BinaryReader::new_with_endianness(&little, Endianness::Big);
+    assert_eq!(reader.read_vec_f64_le().unwrap(), doubles);
+  }
+
+  #[test]
+  fn test_flags() {
+    const NAMES: &[(&str, u32)] = &[("compressed", 0), ("encrypted", 1), ("final", 31)];
+    let mut flags = FlagSet::new(NAMES);
+    flags.set("compressed", true).unwrap().set("final", true).unwrap();
+    assert!(matches!(flags.set("signed", true), Err(BinaryError::InvalidValue(_))));
+
+    let mut writer = BinaryWriter::new();
+    writer.write_flags(flags.bits());
+    let data = writer.get_data();
+    assert_eq!(data, [0x01, 0, 0, 0x80]);
+
+    let mut reader = BinaryReader::new(&data);
+    let flags = FlagSet::from_bits(NAMES, reader.read_flags().unwrap());
+    assert!(flags.is_set("final"));
+    assert!(!flags.is_set("encrypted"));
+    assert_eq!(flags.names(), vec!["compressed", "final"]);
+    // Unnamed bits are ignored.
+    assert_eq!(decode_flags(0b110, NAMES), vec!["encrypted"]);
+  }
+
+  #[test]
+  fn test_write_bytes_as_ref() {
+    let vec: Vec<u8> = vec![1, 2, 3];
+    let array: [u8; 3] = [1, 2, 3];
+    let mut writer = BinaryWriter::new();
+    writer.write_bytes(&vec).write_bytes(vec.clone()).write_bytes(array).write_bytes(b"\x01\x02\x03");
+    writer.write_vec_u8(&array);
+    let data = writer.get_data();
+
+    // Every form produces the same length-prefixed bytes as write_vec_u8.
+    let chunks: Vec<&[u8]> = data.chunks(7).collect();
+    assert_eq!(chunks.len(), 5);
+    assert!(chunks.iter().all(|c| *c == [3, 0, 0, 0, 1, 2, 3]));
+  }
+
+  #[test]
+  fn test_reader_finish() {
+    let mut writer = BinaryWriter::new();
+    writer.write_u32(7).write_u16(9);
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+    reader.read_u32().unwrap();
+    reader.read_u16().unwrap();
+    assert_eq!(reader.finish(), Ok(()));
+
+    // Forgetting the u16 leaves two bytes behind.
+    let mut reader = BinaryReader::new(&data);
+    reader.read_u32().unwrap();
+    assert_eq!(reader.finish(), Err(BinaryError::TrailingBytes { remaining: 2 }));
+  }
+
+  #[test]
+  fn test_unexpected_eof_reports_available_bytes() {
+    // An empty buffer reports that nothing was available
+    let err = BinaryReader::new(&[]).read_u32().unwrap_err();
+    assert_eq!(err, BinaryError::UnexpectedEof { needed: 4, available: 0, offset: 0 });
+    assert_eq!(err.to_string(), "Unexpected end of data at offset 0: needed 4 bytes, but no bytes were left");
+
+    // while a truncated one reports how much of the value was there.
+    let err = BinaryReader::new(&[1, 2, 3]).read_u64().unwrap_err();
+    assert_eq!(err, BinaryError::UnexpectedEof { needed: 8, available: 3, offset: 0 });
+    assert_eq!(err.to_string(), "Unexpected end of data at offset 0: needed 8 bytes, but only 3 were left");
+  }
+
+  #[test]
+  fn test_errors_report_offset() {
+    let mut writer = BinaryWriter::new();
+    writer.write_u32(1).write_u16(2);
+    let data = writer.get_data();
+    let mut reader = BinaryReader::new(&data);
+    reader.read_u32().unwrap();
+    assert_eq!(reader.read_u32(), Err(BinaryError::UnexpectedEof { needed: 4, available: 2, offset: 4 }));
+
+    // Invalid UTF-8 points at the first bad byte, past the prefix and the valid prefix of the text.
+    let mut data = vec![0xAA];
+    data.extend_from_slice(&[4, 0, 0, 0, b'o', b'k', 0xFF, b'!']);
+    let mut reader = BinaryReader::new(&data);
+    reader.read_u8().unwrap();
+    match reader.read_string() {
+      Err(BinaryError::InvalidUtf8 { offset, .. }) => assert_eq!(offset, 7),
+      other => panic!("expected InvalidUtf8, got {:?}", other),
+    }
+    let err = BinaryReader::new(&data[1..]).read_str().unwrap_err();
+    assert!(err.to_string().starts_with("Invalid UTF-8 string at offset 6"));
+  }
+
+  #[test]
+  fn test_fixed_point() {
+    let mut writer = BinaryWriter::new();
+    writer.write_fixed_point(12345, 2).unwrap();
+    writer.write_fixed_point(-7, 0).unwrap();
+    assert!(matches!(writer.write_fixed_point(1, 39), Err(BinaryError::InvalidValue(_))));
+    let data = writer.get_data();
+    assert_eq!(data.len(), 18);
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_fixed_point().unwrap(), (12345, 2));
+    assert_eq!(reader.read_fixed_point().unwrap(), (-7, 0));
+
+    // A scale byte above 38 is rejected on read.
+    let mut bad = 1i64.to_le_bytes().to_vec();
+    bad.push(200);
+    assert!(matches!(BinaryReader::new(&bad).read_fixed_point(), Err(BinaryError::InvalidValue(_))));
+  }
+
+  #[test]
+  fn test_24_bit_integers() {
+    let mut writer = BinaryWriter::new();
+    writer.write_u24(0x12_3456).unwrap().write_i24(-2).unwrap();
+    writer.write_vec_i24(&[-0x80_0000, 0x7F_FFFF]).unwrap();
+    // Out-of-range values are rejected, not masked, and write nothing.
+    assert!(matches!(writer.write_u24(0x100_0000), Err(BinaryError::InvalidValue(_))));
+    assert!(matches!(writer.write_vec_i24(&[0, 0x80_0000]), Err(BinaryError::InvalidValue(_))));
+    let data = writer.get_data();
+    assert_eq!(&data[..6], &[0x56, 0x34, 0x12, 0xFE, 0xFF, 0xFF]);
+    assert_eq!(data.len(), 6 + 4 + 6);
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_u24().unwrap(), 0x12_3456);
+    assert_eq!(reader.read_i24().unwrap(), -2);
+    assert_eq!(reader.read_vec_i24().unwrap(), vec![-0x80_0000, 0x7F_FFFF]);
+    assert_eq!(reader.finish(), Ok(()));
+
+    let mut writer = BinaryWriter::new_with_endianness(Endianness::Big);
+    writer.write_vec_u24(&[1, 0xAB_CDEF]).unwrap();
+    let data = writer.get_data();
+    assert_eq!(&data[4..], &[0, 0, 1, 0xAB, 0xCD, 0xEF]);
+    let mut reader = BinaryReader::new_with_endianness(&data, Endianness::Big);
+    assert_eq!(reader.read_vec_u24().unwrap(), vec![1, 0xAB_CDEF]);
+  }
+
+  #[test]
+  fn test_frame_decoder() {
+    let mut writer = BinaryWriter::new();
+    writer.write_string("hello");
+    let frame = writer.finish_framed().unwrap();
+
+    // Fed one byte at a time, splitting the prefix too, exactly one frame comes out at the end.
+    let mut decoder = FrameDecoder::new();
+    let mut frames = Vec::new();
+    for &byte in &frame {
+      decoder.push(&[byte]);
+      frames.extend(decoder.next_frame().unwrap());
+    }
+    assert_eq!(frames.len(), 1);
+    assert_eq!(BinaryReader::new(&frames[0]).read_string().unwrap(), "hello");
+    assert_eq!(decoder.buffered(), 0);
+
+    // Two frames and the start of a third in one push; the leftover is kept.
+    let mut decoder = FrameDecoder::with_prefix(PrefixWidth::U8, Endianness::Little);
+    decoder.push(&[1, 7, 2, 8, 9, 3, 1]);
+    assert_eq!(decoder.next_frame(), Ok(Some(vec![7])));
+    assert_eq!(decoder.next_frame(), Ok(Some(vec![8, 9])));
+    assert_eq!(decoder.next_frame(), Ok(None));
+    assert_eq!(decoder.next_frame(), Ok(None));
+    decoder.push(&[2, 3]);
+    assert_eq!(decoder.next_frame(), Ok(Some(vec![1, 2, 3])));
+
+    // A varint prefix split across pushes is decoded once it is whole.
+    let mut decoder = FrameDecoder::with_prefix(PrefixWidth::Varint, Endianness::Little);
+    decoder.push(&[0x80]);
+    assert_eq!(decoder.next_frame(), Ok(None));
+    decoder.push(&[0x01]);
+    assert_eq!(decoder.next_frame(), Ok(None));
+    decoder.push(&[0xAA; 128]);
+    assert_eq!(decoder.next_frame(), Ok(Some(vec![0xAA; 128])));
+    assert_eq!(decoder.buffered(), 0);
+
+    // A malformed prefix is an error, not a frame that never completes.
+    let mut decoder = FrameDecoder::with_prefix(PrefixWidth::Varint, Endianness::Little);
+    decoder.push(&[0xFF; 11]);
+    assert!(matches!(decoder.next_frame(), Err(BinaryError::InvalidValue(_))));
+    assert!(decoder.next_frame().is_err());
+  }
+
+  #[test]
+  fn test_reader_checkpoint_restore() {
+    let mut writer = BinaryWriter::new();
+    writer.write_u8(1).write_u32(2).write_u8(3);
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+    reader.read_u8().unwrap();
+    let after_first = reader.checkpoint();
+    assert_eq!(reader.read_u32().unwrap(), 2);
+    reader.restore(after_first);
+    // Backtrack and reinterpret the same bytes.
+    assert_eq!(reader.read_u8().unwrap(), 2);
+    reader.restore(after_first);
+    assert_eq!(reader.read_u32().unwrap(), 2);
+    assert_eq!(reader.read_u8().unwrap(), 3);
+  }
+
+  #[test]
+  #[should_panic(expected = "different buffer")]
+  fn test_reader_restore_foreign_checkpoint() {
+    let checkpoint = BinaryReader::new(&[1, 2, 3]).checkpoint();
+    BinaryReader::new(&[4, 5]).restore(checkpoint);
+  }
+
+  #[cfg(feature = "derive")]
+  #[test]
+  fn test_derive_phantom_marker_round_trip() {
+    #[derive(BinWrite, BinRead, Debug, PartialEq)]
+    struct Marked {
+      value: u32,
+      marker: PhantomData<u8>,
+    }
+
+    let mut writer = BinaryWriter::new();
+    writer.write(&Marked { value: 9, marker: PhantomData });
+    let data = writer.get_data();
+    // The marker adds nothing to the encoding.
+    assert_eq!(data, [9, 0, 0, 0]);
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read::<Marked>().unwrap(), Marked { value: 9, marker: PhantomData });
+    assert_eq!(reader.finish(), Ok(()));
+  }
+
+  #[cfg(feature = "derive")]
+  #[test]
+  fn test_write_array_of_derived_structs() {
+    #[derive(Binary, Debug, PartialEq, Clone, Copy)]
+    struct Vertex {
+      x: f32,
+      y: f32,
+    }
+
+    let triangle = [Vertex { x: 0.0, y: 0.0 }, Vertex { x: 1.0, y: 0.0 }, Vertex { x: 0.0, y: 1.0 }];
+    let mut writer = BinaryWriter::new();
+    writer.write_array(&triangle).write_array(&[7u16, 8]);
+    let data = writer.get_data();
+    // No length prefix: three 8-byte vertices, then two u16s.
+    assert_eq!(data.len(), 3 * 8 + 4);
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_array::<Vertex, 3>().unwrap(), triangle);
+    assert_eq!(reader.read_array::<u16, 2>().unwrap(), [7, 8]);
+    assert!(matches!(reader.read_array::<u8, 1>(), Err(BinaryError::UnexpectedEof { .. })));
+  }
+
+  #[test]
+  fn test_varint() {
+    let mut writer = BinaryWriter::new();
+    writer.write_varint(0).write_varint(127).write_varint(300).write_varint(u64::MAX);
+    let data = writer.get_data();
+    assert_eq!(&data[..4], &[0, 0x7F, 0xAC, 0x02]);
+    assert_eq!(data.len(), 4 + 10);
+
+    let mut reader = BinaryReader::new(&data);
+    for expected in [0, 127, 300, u64::MAX] {
+      assert_eq!(reader.read_varint().unwrap(), expected);
+    }
+
+    // More than 64 bits of payload is rejected rather than wrapping.
+    let mut overlong = vec![0xFF; 9];
+    overlong.push(0x02);
+    assert!(matches!(BinaryReader::new(&overlong).read_varint(), Err(BinaryError::InvalidValue(_))));
+    assert!(matches!(BinaryReader::new(&[0x80; 11]).read_varint(), Err(BinaryError::InvalidValue(_))));
+  }
+
+  #[test]
+  fn test_vec_bool_rle() {
+    let mut bits = vec![false; 1000];
+    bits[10] = true;
+    bits[500] = true;
+    bits[999] = true;
+    let mut writer = BinaryWriter::new();
+    writer.write_vec_bool_rle(&bits);
+    let data = writer.get_data();
+    // Total (2 bytes) then runs 10, 1, 489, 1, 498, 1: far smaller than a 125-byte bitset.
+    assert_eq!(data.len(), 2 + 1 + 1 + 2 + 1 + 2 + 1);
+    assert_eq!(BinaryReader::new(&data).read_vec_bool_rle().unwrap(), bits);
+
+    // A vector starting with true opens with an empty false run.
+    let mut writer = BinaryWriter::new();
+    writer.write_vec_bool_rle(&[true, true, false]);
+    let data = writer.get_data();
+    assert_eq!(data, [3, 0, 2, 1]);
+    assert_eq!(BinaryReader::new(&data).read_vec_bool_rle().unwrap(), vec![true, true, false]);
+
+    // Runs longer than the declared total are rejected.
+    assert!(matches!(BinaryReader::new(&[3, 2, 5]).read_vec_bool_rle(), Err(BinaryError::InvalidValue(_))));
+    assert_eq!(
+      BinaryReader::new(&[0xE8, 0x07, 0xE8, 0x07]).read_vec_bool_rle_bounded(999),
+      Err(BinaryError::LengthOverflow { length: 1000, max: 999 })
+    );
+    // Without an explicit bound, a declared length past the default cap is rejected up front.
+    let mut writer = BinaryWriter::new();
+    writer.write_varint(DEFAULT_MAX_BOOL_RLE_LEN as u64 + 1).write_varint(DEFAULT_MAX_BOOL_RLE_LEN as u64 + 1);
+    let data = writer.get_data();
+    assert!(matches!(BinaryReader::new(&data).read_vec_bool_rle(), Err(BinaryError::LengthOverflow { .. })));
+  }
+
+  #[test]
+  fn test_bulk_vector_reads_match_swapped_path() {
+    // The same values through the native-order bulk copy and through the byte-swapping loop.
+    let ints: Vec<u64> = (0..300u64).map(|i| i.wrapping_mul(0x9E37_79B9_7F4A_7C15)).collect();
+    let floats: Vec<f32> = (0..300).map(|i| i as f32 * 0.5 - 100.0).collect();
+    for endianness in [Endianness::Little, Endianness::Big] {
+      let mut writer = BinaryWriter::new_with_endianness(endianness);
+      writer.write_vec_u64(&ints).write_vec_f32(&floats);
+      let data = writer.get_data();
+
+      let mut reader = BinaryReader::new_with_endianness(&data, endianness);
+      assert_eq!(reader.read_vec_u64().unwrap(), ints);
+      assert_eq!(reader.read_vec_f32().unwrap(), floats);
+      assert_eq!(reader.finish(), Ok(()));
+    }
+  }
+
+  #[test]
+  fn test_vec_with_prefix_width() {
+    let values = vec![1u16, 2, 3];
+    for (prefix, prefix_len) in [
+      (PrefixWidth::U8, 1),
+      (PrefixWidth::U16, 2),
+      (PrefixWidth::U32, 4),
+      (PrefixWidth::U64, 8),
+      (PrefixWidth::Varint, 1),
+    ] {
+      let mut writer = BinaryWriter::new();
+      writer.write_vec_with_prefix(&values, prefix).unwrap();
+      let data = writer.get_data();
+      assert_eq!(data.len(), prefix_len + 6);
+      assert_eq!(data[0], 3);
+      let mut reader = BinaryReader::new(&data);
+      assert_eq!(reader.read_vec_with_prefix::<u16>(prefix).unwrap(), values);
+    }
+
+    // The u32 width matches write_vec
+    let mut writer = BinaryWriter::new();
+    writer.write_vec_with_prefix(&values, PrefixWidth::U32).unwrap().write_vec(&values);
+    let data = writer.get_data();
+    assert_eq!(data[..10], data[10..]);
+
+    // Too many elements for the prefix fails without writing
+    let mut writer = BinaryWriter::new();
+    assert_eq!(
+      writer.write_vec_with_prefix(&[0u8; 256], PrefixWidth::U8).err(),
+      Some(BinaryError::LengthOverflow { length: 256, max: 255 })
+    );
+    assert!(writer.get_data().is_empty());
+  }
+
+  #[test]
+  fn test_bulk_vector_writes_match_scalar_writes() {
+    let doubles: Vec<f64> = (0..300).map(|i| (i as f64).sqrt() - 10.0).collect();
+    let shorts: Vec<i16> = (0..1000).map(|i| (i * 37 - 18_000) as i16).collect();
+    for endianness in [Endianness::Little, Endianness::Big] {
+      let mut bulk = BinaryWriter::new_with_endianness(endianness);
+      bulk.write_vec_f64(&doubles).write_vec_i16(&shorts);
+
+      let mut scalar = BinaryWriter::new_with_endianness(endianness);
+      scalar.write_u32(doubles.len() as u32);
+      doubles.iter().for_each(|&v| {
+        scalar.write_f64(v);
+      });
+      scalar.write_u32(shorts.len() as u32);
+      shorts.iter().for_each(|&v| {
+        scalar.write_i16(v);
+      });
+      assert_eq!(bulk.get_data(), scalar.get_data());
+    }
+
+    // Canonicalized NaNs still go through the per-value path.
+    let mut writer = BinaryWriter::new();
+    writer.canonicalize_nan(true).write_vec_f32(&[f32::from_bits(0x7fc0_0001)]);
+    assert_eq!(writer.get_data()[4..], 0x7fc0_0000u32.to_le_bytes());
+  }
+
+  #[test]
+  fn test_read_vec_u8_ref() {
+    let blob: Vec<u8> = (0..=255).collect();
+    let mut writer = BinaryWriter::new();
+    writer.write_vec_u8(&blob).write_u8(9);
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+    let borrowed = reader.read_vec_u8_ref().unwrap();
+    assert_eq!(borrowed, &blob[..]);
+    // The slice points into the original buffer, right after the length prefix.
+    assert!(std::ptr::eq(borrowed.as_ptr(), data[4..].as_ptr()));
+    assert_eq!(reader.read_u8().unwrap(), 9);
+    assert!(matches!(BinaryReader::new(&[5, 0, 0, 0, 1]).read_vec_u8_ref(), Err(BinaryError::UnexpectedEof { .. })));
+  }
+
+  #[test]
+  fn test_reader_accounting() {
+    let mut writer = BinaryWriter::new();
+    writer.write_u32(7).write_string("hello").write_vec_u16(&[1, 2]);
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data).with_accounting();
+    assert_eq!(reader.read_labeled::<u32>("id").unwrap(), 7);
+    assert_eq!(reader.read_labeled::<String>("name").unwrap(), "hello");
+    reader.labeled("samples", |r| r.read_vec_u16()).unwrap();
+    assert!(reader.read_labeled::<u8>("missing").is_err());
+    let expected = [("id".to_string(), 4), ("name".to_string(), 4 + 5), ("samples".to_string(), 4 + 4)];
+    assert_eq!(reader.account(), &expected);
+
+    // A labeled read that rewinds to before its start records 0 instead of underflowing.
+    let mut reader = BinaryReader::new(&data).with_accounting();
+    let start = reader.checkpoint();
+    reader.read_u32().unwrap();
+    reader.labeled("rewind", |r| {
+      r.restore(start);
+      r.read_u8()
+    }).unwrap();
+    assert_eq!(reader.account(), &[("rewind".to_string(), 0)]);
+
+    // Without accounting, labeled reads behave like plain reads and record nothing.
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_labeled::<u32>("id").unwrap(), 7);
+    assert!(reader.account().is_empty());
+  }
+
+  #[test]
+  fn test_read_bool_lenient() {
+    let data = [0u8, 1, 2, 0xFF];
+    let mut reader = BinaryReader::new(&data);
+    let values: Vec<bool> = (0..4).map(|_| reader.read_bool_lenient().unwrap()).collect();
+    assert_eq!(values, [false, true, true, true]);
+    assert!(reader.read_bool_lenient().is_err());
+
+    // The strict reader still rejects anything but 0 or 1.
+    let mut reader = BinaryReader::new(&data[2..]);
+    assert!(matches!(reader.read_bool(), Err(BinaryError::InvalidValue(_))));
+  }
+
+  #[cfg(feature = "derive")]
+  #[test]
+  fn test_derive_fixed_array_fields() {
+    #[derive(Binary, SizeHint, Debug, PartialEq)]
+    struct Sealed {
+      #[bin(fixed = 16)]
+      nonce: [u8; 16],
+      tags: [u16; 2],
+      body: Vec<u8>,
+    }
+
+    let value = Sealed { nonce: [7; 16], tags: [1, 2], body: vec![9, 9] };
+    let mut writer = BinaryWriter::new();
+    writer.write(&value);
+    let data = writer.get_data();
+    // The arrays carry no length prefix; only the Vec does.
+    assert_eq!(data.len(), 16 + 4 + 4 + 2);
+    assert_eq!(&data[..16], &[7; 16]);
+    assert_eq!(value.serialized_size(), data.len());
+    assert_eq!(BinaryReader::new(&data).read::<Sealed>().unwrap(), value);
+
+    #[derive(Binary, Debug, PartialEq)]
+    #[bin(try_from)]
+    struct Key {
+      id: u32,
+      #[bin(fixed = 8)]
+      bytes: [u8; 8],
+    }
+
+    assert_eq!(<Key as Readable>::SERIALIZED_SIZE, Some(12));
+    let key = Key { id: 3, bytes: *b"abcdefgh" };
+    let mut writer = BinaryWriter::new();
+    writer.write(&key);
+    let data = writer.get_data();
+    assert_eq!(Key::try_from(&data[..]).unwrap(), key);
+
+    // Lengths given by a named constant or a const generic are checked by the compiler instead
+    const NONCE_LEN: usize = 4;
+    #[derive(Binary, Debug, PartialEq)]
+    struct Padded<const N: usize> {
+      #[bin(fixed = 4)]
+      nonce: [u8; NONCE_LEN],
+      #[bin(fixed = 2)]
+      pad: [u8; N],
+    }
+
+    let value = Padded::<2> { nonce: [1, 2, 3, 4], pad: [0, 0] };
+    let mut writer = BinaryWriter::new();
+    writer.write(&value);
+    let data = writer.get_data();
+    assert_eq!(data.len(), 6);
+    assert_eq!(BinaryReader::new(&data).read::<Padded<2>>().unwrap(), value);
+  }
+
+  #[test]
+  fn test_ip_addresses() {
+    let v4 = Ipv4Addr::new(192, 168, 0, 1);
+    let v6 = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+    let mut writer = BinaryWriter::new_with_endianness(Endianness::Little);
+    writer.write_ipv4(v4).write_ipv6(v6).write(&vec![v4, Ipv4Addr::LOCALHOST]);
+    let data = writer.get_data();
+    // Octets go out in network order, whatever the writer's byte order, with no family tag.
+    assert_eq!(&data[..4], &[192, 168, 0, 1]);
+    assert_eq!(&data[4..20], &v6.octets());
+    assert_eq!(v6.serialized_size(), 16);
+
+    let mut reader = BinaryReader::new_with_endianness(&data, Endianness::Little);
+    assert_eq!(reader.read_ipv4().unwrap(), v4);
+    assert_eq!(reader.read_ipv6().unwrap(), v6);
+    assert_eq!(reader.read::<Vec<Ipv4Addr>>().unwrap(), [v4, Ipv4Addr::LOCALHOST]);
+    assert!(reader.read_ipv4().is_err());
+  }
+
+  #[test]
+  fn test_vec_i8_bulk_round_trip() {
+    // Every i8 value, written and read as one block of two's-complement bytes.
+    let values: Vec<i8> = (-128..=127).collect();
+    let mut writer = BinaryWriter::new();
+    writer.write_vec_i8(&values);
+    let data = writer.get_data();
+    assert_eq!(data.len(), 4 + 256);
+    assert_eq!(&data[4..6], &[0x80, 0x81]);
+    assert_eq!(data[4 + 128], 0);
+    assert_eq!(data[4 + 255], 0x7F);
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_vec_i8().unwrap(), values);
+
+    // A count past the end of the buffer still fails cleanly.
+    let mut reader = BinaryReader::new(&data[..100]);
+    assert!(matches!(reader.read_vec_i8(), Err(BinaryError::UnexpectedEof { .. })));
+  }
+
+  #[test]
+  fn test_len_prefixed_sections() {
+    let mut writer = BinaryWriter::new_with_endianness(Endianness::Big);
+    writer
+      .write_len_prefixed(PrefixWidth::U8, |w| {
+        w.write_u16(7).write_string("inner");
+      })
+      .unwrap();
+    // Sections nest, each with its own prefix width.
+    writer
+      .write_len_prefixed(PrefixWidth::Varint, |w| {
+        w.write_len_prefixed(PrefixWidth::U16, |w| {
+          w.write_u32(1);
+        })
+        .unwrap();
+        w.write_u8(2);
+      })
+      .unwrap();
+    // A body too long for its prefix is rejected and nothing is written.
+    let result = writer.write_len_prefixed(PrefixWidth::U8, |w| {
+      w.write_vec_u8(&[0; 300]);
+    });
+    assert!(matches!(result, Err(BinaryError::LengthOverflow { length: 304, max: 255 })));
+    writer.write_u8(0xEE);
+    let data = writer.get_data();
+    assert_eq!(data[0], 2 + 4 + 5);
+    assert_eq!(&data[1..3], &[0, 7]);
+
+    let mut reader = BinaryReader::new_with_endianness(&data, Endianness::Big);
+    let mut first = reader.read_len_prefixed(PrefixWidth::U8).unwrap();
+    assert_eq!(first.read_u16().unwrap(), 7);
+    assert_eq!(first.read_string().unwrap(), "inner");
+    assert!(first.read_u8().is_err());
+
+    let mut second = reader.read_len_prefixed(PrefixWidth::Varint).unwrap();
+    let mut nested = second.read_len_prefixed(PrefixWidth::U16).unwrap();
+    assert_eq!(nested.read_u32().unwrap(), 1);
+    nested.finish().unwrap();
+    assert_eq!(second.read_u8().unwrap(), 2);
+    second.finish().unwrap();
+    assert_eq!(reader.read_u8().unwrap(), 0xEE);
+  }
+
+  #[test]
+  fn test_len_prefixed_section_shares_writer_settings() {
+    // A body that would overflow the parent's size limit fails the parent, and nothing is written.
+    let mut writer = BinaryWriter::with_max_size(10);
+    writer.write_u8(1);
+    let result = writer.write_len_prefixed(PrefixWidth::U8, |w| {
+      w.write_vec_u8(&[0; 20]);
+    });
+    assert_eq!(result.err(), Some(BinaryError::SizeLimitExceeded { limit: 10 }));
+    assert_eq!(writer.finish(), Err(BinaryError::SizeLimitExceeded { limit: 10 }));
+
+    // The prefix counts against the budget too: 4 bytes of body plus a u32 prefix do not fit in 7.
+    let mut writer = BinaryWriter::with_max_size(7);
+    assert!(writer.write_len_prefixed(PrefixWidth::U32, |w| {
+      w.write_u32(5);
+    }).is_err());
+    let mut writer = BinaryWriter::with_max_size(8);
+    writer.write_len_prefixed(PrefixWidth::U32, |w| {
+      w.write_u32(5);
+    }).unwrap();
+    assert_eq!(writer.finish().unwrap().len(), 8);
+
+    // Tracing and NaN canonicalization carry into the body.
+    let mut writer = BinaryWriter::with_trace();
+    writer.canonicalize_nan(true);
+    writer.write_len_prefixed(PrefixWidth::U8, |w| {
+      w.write_f32(f32::from_bits(0x7fc0_0001));
+    }).unwrap();
+    assert_eq!(writer.trace().len(), 2);
+    assert_eq!(writer.trace()[0], Token::Vec(4));
+    assert!(matches!(writer.trace()[1], Token::F32(v) if v.is_nan()));
+    assert_eq!(&writer.get_data()[1..], &f32::NAN.to_le_bytes());
+  }
+
+  #[test]
+  fn test_string_interner() {
+    let mut interner = StringInterner::new();
+    let mut writer = BinaryWriter::new();
+    for label in ["foo", "bar", "foo", "foo"] {
+      writer.write_interned(&mut interner, label).write_u8(1);
+    }
+    assert_eq!(interner.strings(), ["foo", "bar"]);
+    let data = writer.finish_interned(&interner).unwrap();
+    // "foo" is stored once in the table, however often it was written.
+    assert_eq!(data.windows(3).filter(|w| w == b"foo").count(), 1);
+
+    let mut reader = BinaryReader::new(&data);
+    let table = reader.read_string_table().unwrap();
+    let mut labels = Vec::new();
+    for _ in 0..4 {
+      labels.push(reader.read_interned(&table).unwrap());
+      assert_eq!(reader.read_u8().unwrap(), 1);
+    }
+    assert_eq!(labels, ["foo", "bar", "foo", "foo"]);
+    reader.finish().unwrap();
+
+    let bad = [9u8, 0, 0, 0];
+    assert!(matches!(BinaryReader::new(&bad).read_interned(&table), Err(BinaryError::InvalidValue(_))));
+  }
+
+  #[test]
+  fn test_string_utf16() {
+    let text = "h\u{e9}llo \u{1F600}";
+    let mut writer = BinaryWriter::new_with_endianness(Endianness::Big);
+    writer.write_string_utf16(text).write_string_utf16("");
+    let data = writer.get_data();
+    // 6 BMP units plus a surrogate pair; the count and the units are little-endian in a big-endian writer.
+    assert_eq!(&data[..4], &[8, 0, 0, 0]);
+    assert_eq!(&data[4..6], &[b'h', 0]);
+    assert_eq!(&data[6..8], &[0xE9, 0]);
+
+    let mut reader = BinaryReader::new_with_endianness(&data, Endianness::Big);
+    assert_eq!(reader.read_string_utf16().unwrap(), text);
+    assert_eq!(reader.read_string_utf16().unwrap(), "");
+    reader.finish().unwrap();
+
+    // A lone high surrogate after one valid unit is reported where it sits.
+    let bad = [2, 0, 0, 0, b'a', 0, 0x00, 0xD8];
+    assert_eq!(BinaryReader::new(&bad).read_string_utf16(), Err(BinaryError::InvalidChar { value: 0xD800, offset: 6 }));
+    // A truncated unit array is an EOF, not a decoding error.
+    assert!(matches!(BinaryReader::new(&bad[..6]).read_string_utf16(), Err(BinaryError::UnexpectedEof { .. })));
+  }
+
+  #[test]
+  fn test_read_vec_string_length_guard() {
+    // The first string claims more bytes than the whole buffer holds.
+    let mut writer = BinaryWriter::new();
+    writer.write_u32(3).write_u32(1000).write_string("b").write_string("c");
+    let data = writer.get_data();
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_vec_string(), Err(BinaryError::UnexpectedEof { needed: 1008, available: 10, offset: 8 }));
+
+    // A length that fits the buffer but would swallow the later strings' prefixes also fails right
+    // away, rather than after decoding the bytes it claims.
+    let mut writer = BinaryWriter::new();
+    writer.write_u32(2).write_u32(5).write_u8(b'a').write_u8(b'b').write_string("c");
+    let data = writer.get_data();
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_vec_string(), Err(BinaryError::UnexpectedEof { needed: 9, available: 7, offset: 8 }));
+
+    // A count that cannot fit even the length prefixes fails before any string is read.
+    let mut writer = BinaryWriter::new();
+    writer.write_u32(1_000_000).write_string("a");
+    let data = writer.get_data();
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_vec_string(), Err(BinaryError::UnexpectedEof { needed: 4_000_000, available: 5, offset: 4 }));
+
+    let strings = vec!["".to_string(), "x".to_string(), "yz".to_string()];
+    let mut writer = BinaryWriter::new();
+    writer.write_vec_string(&strings);
+    let data = writer.get_data();
+    assert_eq!(BinaryReader::new(&data).read_vec_string().unwrap(), strings);
+  }
+
+  #[test]
+  fn test_checksum_footer() {
+    // Standard check values for the ASCII digits 1 through 9, plus a long run that forces Adler-32
+    // to reduce its sums several times.
+    assert_eq!(ChecksumKind::Crc32.checksum(b"123456789"), 0xCBF4_3926);
+    assert_eq!(ChecksumKind::Adler32.checksum(b"123456789"), 0x091E_01DE);
+    assert_eq!(ChecksumKind::Adler32.checksum(&[0xFF; 100_000]), 0x149A_302C);
+
+    for kind in [ChecksumKind::Crc32, ChecksumKind::Adler32] {
+      let mut writer = BinaryWriter::new();
+      writer.write_u32(42).write_string("payload");
+      let data = writer.finish_with_checksum(kind).unwrap();
+
+      let mut reader = BinaryReader::new_verified(&data, kind).unwrap();
+      assert_eq!(reader.read_u32().unwrap(), 42);
+      assert_eq!(reader.read_string().unwrap(), "payload");
+      // The footer is not part of the readable body.
+      assert_eq!(reader.remaining(), 0);
+      assert!(reader.read_u8().is_err());
+
+      let mut corrupted = data.clone();
+      corrupted[0] ^= 1;
+      assert!(matches!(BinaryReader::new_verified(&corrupted, kind), Err(BinaryError::ChecksumMismatch { .. })));
+    }
+    assert_eq!(
+      BinaryReader::new_verified(&[1, 2, 3], ChecksumKind::Crc32).err(),
+      Some(BinaryError::UnexpectedEof { needed: 4, available: 3, offset: 0 })
+    );
+  }
+
+  #[test]
+  fn test_read_padding() {
+    let mut writer = BinaryWriter::new();
+    writer.write_u8(1).write_padding(4, 0xFF).write_u8(2);
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_u8().unwrap(), 1);
+    reader.read_padding(4, Some(0xFF)).unwrap();
+    assert_eq!(reader.read_u8().unwrap(), 2);
+
+    // Without a fill byte the region is skipped unchecked; with the wrong one nothing is consumed.
+    let mut reader = BinaryReader::new(&data[1..]);
+    let err = reader.read_padding(4, Some(0)).unwrap_err();
+    assert_eq!(err, BinaryError::InvalidValue("padding byte at offset 0 is 0xff, expected 0x00".to_string()));
+    assert_eq!(reader.remaining(), 5);
+    reader.read_padding(4, None).unwrap();
+    assert!(matches!(reader.read_padding(2, None), Err(BinaryError::UnexpectedEof { needed: 2, available: 1, offset: 4 })));
+  }
+
+  #[test]
+  fn test_oversized_length_fails_writer() {
+    let mut writer = BinaryWriter::new();
+    writer.try_write_string("ok").unwrap();
+    let mut expected = BinaryWriter::new();
+    expected.write_string("ok");
+    assert_eq!(writer.get_data(), expected.get_data());
+
+    // A 4 GiB string cannot be built in a test, so feed the oversized count in directly.
+    if let Some(count) = (u32::MAX as usize).checked_add(1) {
+      let mut writer = BinaryWriter::new();
+      writer.write_u8(1);
+      writer.write_count(count);
+      writer.write_u8(2);
+      let err = BinaryError::LengthOverflow { length: count, max: u32::MAX as u64 };
+      assert_eq!(writer.try_write(&3u8).err(), Some(err.clone()));
+      assert_eq!(writer.finish(), Err(err));
+    }
+  }
+
+  #[test]
+  fn test_array_trait_impls() {
+    let rows: Vec<[u8; 3]> = vec![[1, 2, 3], [4, 5, 6]];
+    let mut writer = BinaryWriter::new();
+    writer.write(&rows).write(&[[1u16, 2], [3, 4]]);
+    let data = writer.get_data();
+    // One count for the vector, none for the arrays inside it.
+    assert_eq!(&data[..10], &[2, 0, 0, 0, 1, 2, 3, 4, 5, 6]);
+    assert_eq!(data.len(), 10 + 8);
+    assert_eq!(rows.serialized_size(), 10);
+    assert_eq!(<[[u16; 2]; 2] as Readable>::SERIALIZED_SIZE, Some(8));
+    assert_eq!(<[String; 2] as Readable>::SERIALIZED_SIZE, None);
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read::<Vec<[u8; 3]>>().unwrap(), rows);
+    assert_eq!(reader.read::<[[u16; 2]; 2]>().unwrap(), [[1, 2], [3, 4]]);
+
+    // A failure partway through an array of owned values drops the elements already read.
+    let mut writer = BinaryWriter::new();
+    writer.write_string("a").write_u32(99);
+    let data = writer.get_data();
+    assert!(BinaryReader::new(&data).read::<[String; 2]>().is_err());
+  }
+
+  #[test]
+  fn test_vec_option_round_trip() {
+    let column: Vec<Option<u32>> = vec![Some(1), None, None, Some(4), None, None, None, None, None, Some(10)];
+
+    // Through the traits, each element carries its own presence byte.
+    let mut writer = BinaryWriter::new();
+    writer.write(&column).write(&Some("x".to_string())).write(&None::<String>);
+    let data = writer.get_data();
+    assert_eq!(data.len(), 4 + 10 + 3 * 4 + (1 + 5) + 1);
+    assert_eq!(column.serialized_size(), 4 + 10 + 3 * 4);
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read::<Vec<Option<u32>>>().unwrap(), column);
+    assert_eq!(reader.read::<Option<String>>().unwrap().as_deref(), Some("x"));
+    assert_eq!(reader.read::<Option<String>>().unwrap(), None);
+    assert!(matches!(BinaryReader::new(&[2]).read_option::<u8>(), Err(BinaryError::InvalidValue(_))));
+
+    // Packed, presence costs a bit per element and absent values take no space.
+    let mut writer = BinaryWriter::new();
+    writer.write_vec_option_packed(&column);
+    let data = writer.get_data();
+    assert_eq!(&data[4..6], &[0b0000_1001, 0b0000_0010]);
+    assert_eq!(data.len(), 4 + 2 + 3 * 4);
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_vec_option_packed::<u32>().unwrap(), column);
+    reader.finish().unwrap();
+
+    let mut bad = data.clone();
+    bad[5] |= 0x80;
+    assert!(matches!(BinaryReader::new(&bad).read_vec_option_packed::<u32>(), Err(BinaryError::InvalidValue(_))));
+  }
+
+  #[test]
+  fn test_writer_truncate() {
+    let mut writer = BinaryWriter::new();
+    assert!(writer.is_empty());
+    writer.write_u32(1);
+    let mark = writer.len();
+    assert_eq!(mark, 4);
+
+    // Roll back a tentative record, then write the real one in its place.
+    writer.write_string("draft").write_u64(9);
+    writer.truncate(mark).write_u8(2);
+    // Truncating past the end changes nothing.
+    writer.truncate(100);
+    assert_eq!(writer.len(), 5);
+    assert_eq!(writer.get_data(), [1, 0, 0, 0, 2]);
+
+    let mut writer = BinaryWriter::from_vec(vec![0xAA; 3]);
+    assert_eq!(writer.len(), 3);
+    writer.write_u8(1).truncate(3);
+    assert_eq!(writer.get_data(), [0xAA; 3]);
+  }
+
+  #[test]
+  fn test_varint_prefixed_strings_and_vectors() {
+    let long = "x".repeat(300);
+    let mut writer = BinaryWriter::new();
+    writer.write_string_varint("hello").write_string_varint(&long).write_vec_varint(&[7u16, 8]);
+    let data = writer.get_data();
+    // A 1-byte prefix for 5 bytes, a 2-byte prefix (0xAC 0x02) for 300.
+    assert_eq!(data[0], 5);
+    assert_eq!(&data[1..6], b"hello");
+    assert_eq!(&data[6..8], &[0xAC, 0x02]);
+    assert_eq!(data.len(), 1 + 5 + 2 + 300 + 1 + 4);
+
+    let mut reader = BinaryReader::new(&data);
+    assert_eq!(reader.read_string_varint().unwrap(), "hello");
+    assert_eq!(reader.read_string_varint().unwrap(), long);
+    assert_eq!(reader.read_vec_varint::<u16>().unwrap(), [7, 8]);
+    reader.finish().unwrap();
+
+    // An overlong varint and a length past the end of the buffer both fail cleanly.
+    assert!(matches!(BinaryReader::new(&[0x80; 11]).read_string_varint(), Err(BinaryError::InvalidValue(_))));
+    let claims_too_much = [0xFF, 0xFF, 0x03, b'a'];
+    let result = BinaryReader::new(&claims_too_much).read_string_varint();
+    assert_eq!(result, Err(BinaryError::UnexpectedEof { needed: 65535, available: 1, offset: 3 }));
+  }
+
+  #[test]
+  fn test_next_read_size() {
+    let mut writer = BinaryWriter::new_with_endianness(Endianness::Big);
+    writer
+      .write_u32(1)
+      .write_string("hello")
+      .write_vec_u16(&[1, 2, 3])
+      .write(&vec!["ab".to_string(), "c".to_string()])
+      .write(&Some(7u64));
+    let data = writer.get_data();
+
+    let mut reader = BinaryReader::new_with_endianness(&data, Endianness::Big);
+    assert_eq!(reader.next_read_size::<u32>(), Some(4));
+    assert_eq!(reader.next_read_size::<usize>(), Some(8));
+    reader.read_u32().unwrap();
+    assert_eq!(reader.next_read_size::<String>(), Some(4 + 5));
+    // Peeking does not advance.
+    assert_eq!(reader.read_string().unwrap(), "hello");
+    assert_eq!(reader.next_read_size::<Vec<u16>>(), Some(4 + 3 * 2));
+    reader.read_vec_u16().unwrap();
+    assert_eq!(reader.next_read_size::<Vec<String>>(), Some(4 + (4 + 2) + (4 + 1)));
+    reader.read::<Vec<String>>().unwrap();
+    assert_eq!(reader.next_read_size::<Option<u64>>(), Some(1 + 8));
+
+    // With only part of a string buffered, the prefix still gives its full size, but nothing can
+    // be told until the prefix itself has arrived.
+    let reader = BinaryReader::new_with_endianness(&data[4..10], Endianness::Big);
+    assert_eq!(reader.next_read_size::<String>(), Some(9));
+    let reader = BinaryReader::new_with_endianness(&data[4..7], Endianness::Big);
+    assert_eq!(reader.next_read_size::<String>(), None);
+    assert_eq!(reader.peek_length(), Err(BinaryError::UnexpectedEof { needed: 4, available: 3, offset: 0 }));
   }
 }